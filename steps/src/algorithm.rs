@@ -0,0 +1,376 @@
+//! Common interface of step counting algorithms.
+//!
+//! Every algorithm takes the same [`StepInput`] and uses only data which it needs,
+//! so algorithm could be chosen or combined per device at runtime:
+//!
+//! ```
+//! use steps::{Autocorrelation, PeakDetection, StepAlgorithm, StepInput, most_confident};
+//!
+//! let algorithms: Vec<Box<dyn StepAlgorithm>> = vec![
+//!     Box::new(PeakDetection::default()),
+//!     Box::new(Autocorrelation::default()),
+//! ];
+//!
+//! let input = StepInput::default();
+//!
+//! assert!(most_confident(&algorithms, &input).is_none());
+//! ```
+
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    Accelerometer, AutocorrelationConfig, Gps, SPEED_THRESHOLD_KMPHR, StepCounterConfig, StepEvent,
+    gait_windows, movement_from_gps, step_events_with, steps_from_movements, virtual_steps,
+};
+
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+
+/// Data available to count steps.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct StepInput {
+    /// Raw accelerometer data sorted by timestamp in asc order
+    pub accelerometer: Vec<Accelerometer>,
+    /// GPS data sorted by timestamp in asc order
+    pub gps: Vec<Gps>,
+    /// Metabolic equivalent of task of the activity
+    pub met: Option<f64>,
+    /// Height of person in meters
+    pub height: Option<f64>,
+    /// Weight of person in kilograms
+    pub weight: Option<f64>,
+}
+
+/// Steps counted by [`StepAlgorithm`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StepResult {
+    pub count: u64,
+    /// Single steps, empty if algorithm doesn't detect them
+    pub events: Vec<StepEvent>,
+    /// How much result could be trusted in `[0, 1]`, meaning depends on algorithm
+    pub confidence: f64,
+}
+
+/// Step counting algorithm.
+pub trait StepAlgorithm {
+    /// Name of algorithm to report which one produced result
+    fn name(&self) -> &'static str;
+
+    /// Count steps, `None` if `input` doesn't have data required by algorithm
+    fn count(&self, input: &StepInput) -> Option<StepResult>;
+}
+
+/// Run all `algorithms` and return name and result of the most confident one.
+pub fn most_confident(
+    algorithms: &[Box<dyn StepAlgorithm>],
+    input: &StepInput,
+) -> Option<(&'static str, StepResult)> {
+    algorithms
+        .iter()
+        .filter_map(|this| this.count(input).map(|result| (this.name(), result)))
+        .max_by(|(_, a), (_, b)| a.confidence.total_cmp(&b.confidence))
+}
+
+/// [Peak detection](crate::step_events_with) of accelerometer data.
+///
+/// Confidence is regularity of time between steps: `1 - std / mean`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeakDetection {
+    pub config: StepCounterConfig,
+}
+
+impl StepAlgorithm for PeakDetection {
+    fn name(&self) -> &'static str {
+        "peak_detection"
+    }
+
+    fn count(&self, input: &StepInput) -> Option<StepResult> {
+        if input.accelerometer.is_empty() {
+            return None;
+        }
+
+        let events = step_events_with(&self.config, input.accelerometer.iter().cloned());
+
+        // time goes backwards between segments of recording, such intervals are skipped
+        let intervals = events
+            .windows(2)
+            .filter_map(|this| this[1].timestamp.checked_sub(this[0].timestamp))
+            .map(|this| this.as_secs_f64())
+            .collect::<Vec<_>>();
+
+        let confidence = match intervals.is_empty() {
+            true => 0.0,
+            false => {
+                let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+                let std = (intervals
+                    .iter()
+                    .map(|this| (this - mean).powi(2))
+                    .sum::<f64>()
+                    / intervals.len() as f64)
+                    .sqrt();
+
+                (1.0 - std / mean).clamp(0.0, 1.0)
+            }
+        };
+
+        Some(StepResult {
+            count: events.len() as u64,
+            events,
+            confidence,
+        })
+    }
+}
+
+/// [Autocorrelation](crate::gait_windows) of accelerometer data.
+///
+/// Confidence is autocorrelation of windows weighted by their steps.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Autocorrelation {
+    pub config: AutocorrelationConfig,
+}
+
+impl StepAlgorithm for Autocorrelation {
+    fn name(&self) -> &'static str {
+        "autocorrelation"
+    }
+
+    fn count(&self, input: &StepInput) -> Option<StepResult> {
+        if input.accelerometer.is_empty() {
+            return None;
+        }
+
+        let windows = gait_windows(&self.config, input.accelerometer.iter().cloned());
+
+        let steps = windows.iter().map(|this| this.steps).sum::<f64>();
+        let confidence = match steps > 0.0 {
+            true => {
+                windows
+                    .iter()
+                    .map(|this| this.correlation * this.steps)
+                    .sum::<f64>()
+                    / steps
+            }
+            false => 0.0,
+        };
+
+        Some(StepResult {
+            count: steps.round() as u64,
+            events: Vec::new(),
+            confidence: confidence.clamp(0.0, 1.0),
+        })
+    }
+}
+
+/// [Steps from GPS](crate::steps_from_gps), requires [`StepInput::height`].
+///
+/// Confidence is part of time which was counted as walking or running.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpsSteps {
+    /// See [`steps_from_gps`](crate::steps_from_gps)
+    pub upper_threshold_kmphr: Option<f64>,
+}
+
+impl StepAlgorithm for GpsSteps {
+    fn name(&self) -> &'static str {
+        "gps"
+    }
+
+    fn count(&self, input: &StepInput) -> Option<StepResult> {
+        let height = input.height?;
+
+        if input.gps.len() < 2 {
+            return None;
+        }
+
+        let upper_threshold_kmphr = self.upper_threshold_kmphr.unwrap_or(SPEED_THRESHOLD_KMPHR);
+
+        let movements = movement_from_gps(input.gps.iter().cloned());
+
+        let (total, walking) = movements.iter().fold((0.0, 0.0), |(total, walking), this| {
+            let duration = this.duration.as_secs_f64();
+
+            match this.speed_kmhr() <= upper_threshold_kmphr {
+                true => (total + duration, walking + duration),
+                false => (total + duration, walking),
+            }
+        });
+
+        let count = steps_from_movements(&movements, height, upper_threshold_kmphr);
+
+        Some(StepResult {
+            count: count as u64,
+            events: Vec::new(),
+            confidence: match total > 0.0 {
+                true => walking / total,
+                false => 0.0,
+            },
+        })
+    }
+}
+
+/// [Virtual steps](crate::virtual_steps), requires [`StepInput::met`] and [`StepInput::weight`].
+///
+/// It is rough estimation from energy expenditure, so confidence is constant [`Self::CONFIDENCE`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VirtualSteps;
+
+impl VirtualSteps {
+    pub const CONFIDENCE: f64 = 0.25;
+}
+
+impl StepAlgorithm for VirtualSteps {
+    fn name(&self) -> &'static str {
+        "virtual_steps"
+    }
+
+    fn count(&self, input: &StepInput) -> Option<StepResult> {
+        Some(StepResult {
+            count: virtual_steps(input.met?, input.weight?),
+            events: Vec::new(),
+            confidence: Self::CONFIDENCE,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::steps_from_gps;
+
+    use super::*;
+
+    fn walking(seconds: u32) -> Vec<Accelerometer> {
+        (0..seconds * 25)
+            .map(|i| {
+                let timestamp = Duration::from_millis(u64::from(i) * 40);
+                let t = timestamp.as_secs_f64();
+
+                Accelerometer {
+                    timestamp,
+                    x: 0.0,
+                    y: 0.0,
+                    z: 1.0 + 0.3 * (2.0 * std::f64::consts::PI * 2.0 * t).sin(),
+                }
+            })
+            .collect()
+    }
+
+    fn algorithms() -> Vec<Box<dyn StepAlgorithm>> {
+        vec![
+            Box::new(PeakDetection::default()),
+            Box::new(Autocorrelation::default()),
+            Box::new(GpsSteps::default()),
+            Box::new(VirtualSteps),
+        ]
+    }
+
+    #[test]
+    fn missing_data() {
+        let input = StepInput::default();
+
+        assert!(algorithms().iter().all(|this| this.count(&input).is_none()));
+    }
+
+    #[test]
+    fn virtual_steps_requires_met_and_weight() {
+        let input = StepInput {
+            met: Some(3.0),
+            ..Default::default()
+        };
+
+        assert_eq!(VirtualSteps.count(&input), None);
+
+        let input = StepInput {
+            met: Some(3.0),
+            weight: Some(80.0),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            VirtualSteps.count(&input).map(|this| this.count),
+            Some(virtual_steps(3.0, 80.0))
+        );
+    }
+
+    #[test]
+    fn gps_matches_steps_from_gps() {
+        let gps = vec![
+            Gps {
+                timestamp: Duration::from_secs(1000),
+                latitude: 49.235835445219784,
+                longitude: 28.48586563389628,
+                altitude: None,
+                horizontal_accuracy: None,
+                hdop: None,
+            },
+            Gps {
+                timestamp: Duration::from_secs(2000),
+                latitude: 49.23297532196681,
+                longitude: 28.493329182275833,
+                altitude: None,
+                horizontal_accuracy: None,
+                hdop: None,
+            },
+        ];
+
+        let input = StepInput {
+            gps: gps.clone(),
+            height: Some(1.9),
+            ..Default::default()
+        };
+
+        let result = GpsSteps::default().count(&input).unwrap();
+
+        assert_eq!(result.count, steps_from_gps(gps, 1.9, None) as u64);
+        assert_eq!(result.confidence, 1.0);
+    }
+
+    #[test]
+    fn accelerometer_algorithms_agree() {
+        let input = StepInput {
+            accelerometer: walking(60),
+            met: Some(3.0),
+            weight: Some(80.0),
+            ..Default::default()
+        };
+
+        let peak = PeakDetection::default().count(&input).unwrap();
+        let autocorrelation = Autocorrelation::default().count(&input).unwrap();
+
+        assert_eq!(peak.count, peak.events.len() as u64);
+        assert!(peak.count.abs_diff(autocorrelation.count) < 10);
+        assert!(peak.confidence > 0.9);
+
+        let (name, result) = most_confident(&algorithms(), &input).unwrap();
+
+        assert_ne!(name, VirtualSteps.name());
+        assert!(result.confidence > VirtualSteps::CONFIDENCE);
+    }
+
+    #[test]
+    fn peak_detection_time_going_backwards() {
+        let mut accelerometer = walking(60);
+        accelerometer.extend(walking(60));
+
+        let input = StepInput {
+            accelerometer,
+            ..Default::default()
+        };
+
+        let peak = PeakDetection::default().count(&input).unwrap();
+
+        assert!(
+            peak.events
+                .windows(2)
+                .any(|this| this[1].timestamp < this[0].timestamp)
+        );
+        assert!(peak.confidence > 0.9, "{}", peak.confidence);
+    }
+}
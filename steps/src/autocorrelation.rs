@@ -0,0 +1,355 @@
+//! Steps by autocorrelation.
+//!
+//! Signal is split into windows and dominant gait period of every window
+//! is estimated by normalized autocorrelation:
+//!
+//! ```notrust
+//! r(k) = Σ x(i)x(i+k) / √(Σ x(i)² Σ x(i+k)²)
+//! ```
+//!
+//! where `x` is magnitude without its mean in window and `k` is lag
+//! between [`AutocorrelationConfig::min_period`] and [`AutocorrelationConfig::max_period`].
+//!
+//! Signal of step repeats at every multiple of step period e.g. at stride with arm swing
+//! or asymmetry of left and right step, so the best lag is halved while autocorrelation
+//! at half of it is at least [`AutocorrelationConfig::min_half_period_correlation`].
+//! Window has `window / period` steps for the shortest period.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{
+    Accelerometer, DataPoint, Preprocessing, StepCounterConfig, StepsError, validate_accelerometer,
+};
+
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+
+const SAMPLE_TIME: Duration = Duration::from_millis(20);
+const MAX_GAP: Duration = Duration::from_secs(1);
+const WINDOW: Duration = Duration::from_secs(5);
+const MIN_PERIOD: Duration = Duration::from_millis(250);
+const MIN_HALF_PERIOD_CORRELATION: f64 = 0.0;
+const MAX_PERIOD: Duration = Duration::from_millis(1500);
+const MIN_CORRELATION: f64 = 0.4;
+const MIN_STD: f64 = 0.05;
+
+/// Tuning parameters of steps counting by autocorrelation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct AutocorrelationConfig {
+    /// Processing of raw accelerometer data before resampling
+    pub preprocessing: Preprocessing,
+    /// Time between two points of resampled signal
+    pub sample_time: Duration,
+    /// Signal is split into independent segments on bigger gaps
+    pub max_gap: Option<Duration>,
+    /// Length of window with single gait period, must be longer than two [`Self::max_period`]
+    pub window: Duration,
+    /// Shortest period of step
+    pub min_period: Duration,
+    /// Longest period of step or stride
+    pub max_period: Duration,
+    /// Minimal autocorrelation at half of period to treat period as two steps e.g. stride,
+    /// `0` accepts half period when signal at it is in phase
+    pub min_half_period_correlation: f64,
+    /// Minimal autocorrelation in `[0, 1]` to consider window periodic
+    pub min_correlation: f64,
+    /// Minimal standard deviation of magnitude to consider window as movement
+    pub min_std: f64,
+}
+
+impl Default for AutocorrelationConfig {
+    fn default() -> Self {
+        Self {
+            preprocessing: Preprocessing::default(),
+            sample_time: SAMPLE_TIME,
+            max_gap: Some(MAX_GAP),
+            window: WINDOW,
+            min_period: MIN_PERIOD,
+            max_period: MAX_PERIOD,
+            min_half_period_correlation: MIN_HALF_PERIOD_CORRELATION,
+            min_correlation: MIN_CORRELATION,
+            min_std: MIN_STD,
+        }
+    }
+}
+
+/// Gait estimation of a single window.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GaitWindow {
+    pub start: Duration,
+    pub end: Duration,
+    /// Period of step, `None` if window isn't periodic
+    pub period: Option<Duration>,
+    /// Autocorrelation at the best lag, which is a multiple of period
+    pub correlation: f64,
+    /// Number of steps in window, fractional as window rarely contains whole steps
+    pub steps: f64,
+}
+
+/// Count steps by autocorrelation using [`AutocorrelationConfig::default`].
+pub fn steps_count_autocorrelation(input: impl IntoIterator<Item = Accelerometer>) -> usize {
+    steps_count_autocorrelation_with(&AutocorrelationConfig::default(), input)
+}
+
+/// Count steps by autocorrelation tuned by `config`.
+pub fn steps_count_autocorrelation_with(
+    config: &AutocorrelationConfig,
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> usize {
+    gait_windows(config, input)
+        .iter()
+        .map(|this| this.steps)
+        .sum::<f64>()
+        .round() as usize
+}
+
+/// Count steps by autocorrelation tuned by `config`.
+///
+/// Fails instead of returning `0` if config can't produce windows, input isn't sorted
+/// by timestamp, has NaN or infinite axes or is shorter than [`AutocorrelationConfig::window`].
+pub fn try_steps_count_autocorrelation_with(
+    config: &AutocorrelationConfig,
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> Result<usize, StepsError> {
+    let input = input.into_iter().collect::<Vec<_>>();
+
+    let invalid = |parameter, reason| Err(StepsError::InvalidConfig { parameter, reason });
+
+    if config.sample_time.is_zero() {
+        return invalid("sample_time", "must be above zero");
+    }
+
+    if config.max_period < config.min_period {
+        return invalid("max_period", "must be above `min_period`");
+    }
+
+    if config.window <= config.max_period.saturating_mul(2) {
+        return invalid("window", "must be above two `max_period`");
+    }
+
+    validate_accelerometer(&input, config.window)?;
+
+    Ok(steps_count_autocorrelation_with(config, input))
+}
+
+/// Estimate gait period of every window.
+pub fn gait_windows(
+    config: &AutocorrelationConfig,
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> Vec<GaitWindow> {
+    let pipeline = StepCounterConfig {
+        preprocessing: config.preprocessing,
+        interpolation_time: config.sample_time,
+        interpolation_max_gap: config.max_gap,
+        ..Default::default()
+    };
+
+    let sample_time = config.sample_time.as_secs_f64();
+    if sample_time == 0.0 {
+        return Vec::new();
+    }
+
+    let window_length = (config.window.as_secs_f64() / sample_time).round() as usize;
+    let min_lag = ((config.min_period.as_secs_f64() / sample_time).round() as usize).max(1);
+    let max_lag = (config.max_period.as_secs_f64() / sample_time).round() as usize;
+
+    if max_lag < min_lag || window_length <= 2 * max_lag {
+        return Vec::new();
+    }
+
+    let after_processing = crate::steps::preprocessing(&pipeline, input);
+
+    crate::steps::interpolation(&pipeline, after_processing)
+        .iter()
+        .flat_map(|segment| {
+            segment
+                .chunks(window_length)
+                // tail of segment is used only if it is long enough to contain max period twice
+                .filter(|this| this.len() > 2 * max_lag)
+                .map(|this| gait_window(config, this, min_lag, max_lag))
+        })
+        .collect()
+}
+
+fn gait_window(
+    config: &AutocorrelationConfig,
+    window: &[DataPoint],
+    min_lag: usize,
+    max_lag: usize,
+) -> GaitWindow {
+    let first = window.first().expect("window isn't empty");
+    let last = window.last().expect("window isn't empty");
+
+    let start = first.timestamp;
+    let end = last.timestamp + config.sample_time;
+
+    let mean = window.iter().map(|this| this.magnitude).sum::<f64>() / window.len() as f64;
+    let signal = window
+        .iter()
+        .map(|this| this.magnitude - mean)
+        .collect::<Vec<_>>();
+
+    let std = (signal.iter().map(|this| this.powi(2)).sum::<f64>() / signal.len() as f64).sqrt();
+
+    let still = GaitWindow {
+        start,
+        end,
+        period: None,
+        correlation: 0.0,
+        steps: 0.0,
+    };
+
+    if std < config.min_std {
+        return still;
+    }
+
+    let Some((lag, correlation)) = (min_lag..=max_lag)
+        .map(|lag| (lag, autocorrelation(&signal, lag)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    else {
+        return still;
+    };
+
+    if correlation < config.min_correlation {
+        return GaitWindow {
+            correlation,
+            ..still
+        };
+    }
+
+    // autocorrelation is high at every multiple of step, so period is halved
+    // while signal at half of it is in phase e.g. stride is split into steps
+    let mut lag = lag;
+
+    while let Some((half, _)) = (lag / 2..=lag.div_ceil(2))
+        .filter(|this| *this >= min_lag)
+        .map(|this| (this, autocorrelation(&signal, this)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .filter(|(_, correlation)| *correlation >= config.min_half_period_correlation)
+    {
+        lag = half;
+    }
+
+    let period = config.sample_time * lag as u32;
+
+    GaitWindow {
+        start,
+        end,
+        period: Some(period),
+        correlation,
+        steps: (end - start).as_secs_f64() / period.as_secs_f64(),
+    }
+}
+
+/// Normalized autocorrelation of `signal` at `lag`
+pub(crate) fn autocorrelation(signal: &[f64], lag: usize) -> f64 {
+    let (head, tail) = (&signal[..signal.len() - lag], &signal[lag..]);
+
+    let product = head.iter().zip(tail).map(|(a, b)| a * b).sum::<f64>();
+    let energy = (head.iter().map(|this| this.powi(2)).sum::<f64>()
+        * tail.iter().map(|this| this.powi(2)).sum::<f64>())
+    .sqrt();
+
+    if energy == 0.0 {
+        return 0.0;
+    }
+
+    product / energy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 25Hz samples of vertical acceleration for `seconds` seconds
+    fn samples(seconds: u32, f: impl Fn(f64) -> f64) -> Vec<Accelerometer> {
+        (0..seconds * 25)
+            .map(|i| {
+                let timestamp = Duration::from_millis(u64::from(i) * 40);
+
+                Accelerometer {
+                    timestamp,
+                    x: 0.0,
+                    y: 0.0,
+                    z: f(timestamp.as_secs_f64()),
+                }
+            })
+            .collect()
+    }
+
+    fn wave(frequency: f64) -> impl Fn(f64) -> f64 {
+        move |t| 1.0 + 0.3 * (2.0 * core::f64::consts::PI * frequency * t).sin()
+    }
+
+    #[test]
+    fn steps_period() {
+        let actual = steps_count_autocorrelation(samples(60, wave(2.0)));
+
+        assert!((115..=125).contains(&actual), "{actual}");
+    }
+
+    /// Stride with asymmetry of left and right step at `stride` frequency and steps at its harmonic
+    fn stride(stride: f64, asymmetry: f64) -> impl Fn(f64) -> f64 {
+        move |t| {
+            1.0 + asymmetry * (2.0 * core::f64::consts::PI * stride * t).sin()
+                + 0.3 * (2.0 * core::f64::consts::PI * 2.0 * stride * t).sin()
+        }
+    }
+
+    #[test]
+    fn stride_period() {
+        let actual = steps_count_autocorrelation(samples(60, stride(0.9, 0.1)));
+
+        assert!((103..=113).contains(&actual), "{actual}");
+    }
+
+    #[test]
+    fn running_cadence() {
+        // 2.9 steps per second, stride is shorter than 750ms
+        let actual = steps_count_autocorrelation(samples(60, stride(1.45, 0.15)));
+
+        assert!((169..=179).contains(&actual), "{actual}");
+    }
+
+    #[test]
+    fn slow_walking() {
+        // single step is longer than 750ms
+        let actual = steps_count_autocorrelation(samples(60, wave(1.1)));
+
+        assert!((61..=71).contains(&actual), "{actual}");
+    }
+
+    #[test]
+    fn still() {
+        let actual = steps_count_autocorrelation(samples(60, |_| 1.0));
+
+        assert_eq!(actual, 0);
+    }
+
+    #[test]
+    fn window_shorter_than_period() {
+        // window fits max period once but not twice
+        for window in [2000, 3000] {
+            let config = AutocorrelationConfig {
+                window: Duration::from_millis(window),
+                ..Default::default()
+            };
+
+            assert!(gait_windows(&config, samples(60, wave(2.0))).is_empty());
+            assert!(matches!(
+                try_steps_count_autocorrelation_with(&config, samples(60, wave(2.0))),
+                Err(StepsError::InvalidConfig {
+                    parameter: "window",
+                    ..
+                })
+            ));
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::{path::PathBuf, time::Duration};
+
+use steps::{AnnotatedRecording, StepCounterConfig, TOLERANCE, evaluate, step_events_with};
+
+#[derive(Debug, clap::Parser)]
+pub struct Args {
+    /// Input csv files or directories with annotated accelerometer data
+    #[arg(required = true)]
+    pub input: Vec<PathBuf>,
+    /// Json file with step counter config, default config if not set
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Maximal time in milliseconds between predicted and annotated step to match them
+    #[arg(short, long, default_value_t = TOLERANCE.as_millis() as u64)]
+    pub tolerance: u64,
+    /// Output csv report. _Note_: will truncate old file if exists
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let Args {
+        input,
+        config,
+        tolerance,
+        output,
+    } = <Args as clap::Parser>::parse();
+
+    let config = match config {
+        Some(path) => serde_json::from_slice::<StepCounterConfig>(
+            &std::fs::read(path).map_err(|e| format!("Failed to read config. Reason: {e}"))?,
+        )
+        .map_err(|e| format!("Failed to parse config. Reason: {e}"))?,
+        None => StepCounterConfig::default(),
+    };
+
+    let mut recordings = Vec::new();
+
+    for path in input {
+        match path.is_dir() {
+            true => recordings.extend(AnnotatedRecording::load_dir(path)?),
+            false => recordings.push(AnnotatedRecording::load(path)?),
+        }
+    }
+
+    let evaluations = evaluate(
+        recordings.into_iter().map(|this| {
+            let predicted = step_events_with(&config, this.samples.iter().cloned())
+                .into_iter()
+                .map(|this| this.timestamp)
+                .collect();
+            let expected = this.steps();
+
+            (this.name, predicted, expected)
+        }),
+        Duration::from_millis(tolerance),
+    );
+
+    println!(
+        "{:<24} {:>8} {:>9} {:>6} {:>6} {:>6} {:>9} {:>6} {:>6}",
+        "name", "expected", "predicted", "tp", "fp", "fn", "precision", "recall", "f1"
+    );
+
+    for this in &evaluations {
+        println!(
+            "{:<24} {:>8} {:>9} {:>6} {:>6} {:>6} {:>9.3} {:>6.3} {:>6.3}",
+            this.name,
+            this.expected,
+            this.predicted,
+            this.true_positives,
+            this.false_positives,
+            this.false_negatives,
+            this.precision,
+            this.recall,
+            this.f1
+        );
+    }
+
+    if let Some(output) = output {
+        let mut writer = csv::Writer::from_path(&output)
+            .map_err(|e| format!("Failed to create report. Reason: {e}"))?;
+
+        for this in &evaluations {
+            writer
+                .serialize(this)
+                .map_err(|e| format!("Failed to write report. Reason: {e}"))?;
+        }
+
+        writer
+            .flush()
+            .map_err(|e| format!("Failed to write report. Reason: {e}"))?;
+
+        println!("Save to {}", output.to_string_lossy());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use std::{path::PathBuf, time::Duration};
+
+use steps::{AnnotatedRecording, ML_MAX_DEPTH, ML_WINDOW_SIZE, StepModel, TOLERANCE, evaluate};
+
+#[derive(Debug, clap::Parser)]
+pub struct Args {
+    /// Input csv files with annotated accelerometer data,
+    /// subject is the part of file name before `_` e.g. `P13`
+    #[arg(required = true)]
+    pub input: Vec<PathBuf>,
+    /// Output model file. _Note_: will truncate old file if exists
+    #[arg(short, long, default_value_os_t = std::env::current_dir().unwrap_or_default().join("machine_counting.json"))]
+    pub output: PathBuf,
+    /// Number of samples in window
+    #[arg(short, long, default_value_t = ML_WINDOW_SIZE)]
+    pub window_size: usize,
+    /// Maximal depth of decision tree
+    #[arg(short, long, default_value_t = ML_MAX_DEPTH)]
+    pub max_depth: usize,
+    /// Maximal time in milliseconds between predicted and annotated step to match them
+    #[arg(short, long, default_value_t = TOLERANCE.as_millis() as u64)]
+    pub tolerance: u64,
+    /// Don't save changes
+    #[arg(short, long, default_value_t = false, required = false)]
+    pub dry: bool,
+    /// Print result to stdout
+    #[arg(short, long, default_value_t = false, required = false)]
+    pub print: bool,
+}
+
+fn subject(recording: &AnnotatedRecording) -> &str {
+    recording.name.split('_').next().unwrap_or(&recording.name)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let Args {
+        input,
+        output,
+        window_size,
+        max_depth,
+        tolerance,
+        dry,
+        print,
+    } = <Args as clap::Parser>::parse();
+
+    let recordings = input
+        .iter()
+        .map(AnnotatedRecording::load)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut subjects = recordings.iter().map(subject).collect::<Vec<_>>();
+    subjects.sort();
+    subjects.dedup();
+
+    // leave-one-subject-out validation shows how model works for unseen person
+    if subjects.len() > 1 {
+        for held_out in &subjects {
+            let (train, test) = recordings
+                .iter()
+                .partition::<Vec<_>, _>(|this| subject(this) != *held_out);
+
+            let model = StepModel::train(
+                train.iter().map(|this| this.labeled()),
+                window_size,
+                Some(max_depth),
+            )?;
+
+            let total = evaluate(
+                test.iter().map(|this| {
+                    (
+                        this.name.clone(),
+                        model.step_timestamps(this.samples.iter().cloned()),
+                        this.steps(),
+                    )
+                }),
+                Duration::from_millis(tolerance),
+            )
+            .pop()
+            .expect("evaluate always returns total");
+
+            println!(
+                "Held out {held_out}: expected {}, predicted {}, f1 {:.3} (precision {:.3}, recall {:.3})",
+                total.expected, total.predicted, total.f1, total.precision, total.recall
+            );
+        }
+    }
+
+    let recordings = recordings
+        .iter()
+        .map(AnnotatedRecording::labeled)
+        .collect::<Vec<_>>();
+
+    println!(
+        "Number of records for training: {}",
+        recordings.iter().map(Vec::len).sum::<usize>()
+    );
+
+    let model = StepModel::train(recordings, window_size, Some(max_depth))?;
+
+    if print {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&model).expect("serde serialization can't fail")
+        );
+    }
+
+    if !dry {
+        println!("Save to {}", output.to_string_lossy());
+
+        std::fs::write(
+            output,
+            serde_json::to_string(&model).expect("serde serialization can't fail"),
+        )
+        .map_err(|e| format!("Failed to save mode. Reason: {e}"))?;
+    }
+
+    println!("Done!");
+
+    Ok(())
+}
@@ -0,0 +1,246 @@
+use std::{path::PathBuf, time::Duration};
+
+use steps::{
+    AnnotatedRecording, Evaluation, StepCounterConfig, TOLERANCE, evaluate, step_events_with,
+};
+
+const FILTER_STD: [f64; 5] = [0.2, 0.35, 0.5, 0.75, 1.0];
+const SCORING_SIZE: [usize; 6] = [15, 25, 35, 45, 55, 75];
+const THRESHOLD: [f64; 6] = [0.6, 0.9, 1.2, 1.5, 1.8, 2.4];
+const TIME_THRESHOLD: [u64; 5] = [150, 200, 250, 300, 400];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Search {
+    /// Every combination of predefined values
+    Grid,
+    /// Random values from the same ranges as grid
+    Random,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct Args {
+    /// Input csv files or directories with annotated accelerometer data,
+    /// subject is the part of file name before `_` e.g. `P13`
+    #[arg(required = true)]
+    pub input: Vec<PathBuf>,
+    /// Json file with step counter config used for parameters which aren't tuned
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Output config file. _Note_: will truncate old file if exists
+    #[arg(short, long, default_value_os_t = std::env::current_dir().unwrap_or_default().join("step_counter_config.json"))]
+    pub output: PathBuf,
+    /// How candidates are generated
+    #[arg(short, long, value_enum, default_value_t = Search::Grid)]
+    pub search: Search,
+    /// Number of candidates for random search
+    #[arg(short = 'n', long, default_value_t = 200)]
+    pub samples: usize,
+    /// Seed of random search
+    #[arg(long, default_value_t = 42)]
+    pub seed: u64,
+    /// Maximal time in milliseconds between predicted and annotated step to match them
+    #[arg(short, long, default_value_t = TOLERANCE.as_millis() as u64)]
+    pub tolerance: u64,
+    /// Don't save changes
+    #[arg(short, long, default_value_t = false, required = false)]
+    pub dry: bool,
+}
+
+/// Xorshift generator, it is enough to sample parameters
+#[derive(Debug)]
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn uniform(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn candidates(
+    base: &StepCounterConfig,
+    search: Search,
+    samples: usize,
+    seed: u64,
+) -> Vec<StepCounterConfig> {
+    let candidate =
+        |filter_std, scoring_size, detection_threshold, time_threshold| StepCounterConfig {
+            filter_std,
+            scoring_size,
+            detection_threshold,
+            time_threshold: Duration::from_millis(time_threshold),
+            ..base.clone()
+        };
+
+    match search {
+        Search::Grid => FILTER_STD
+            .iter()
+            .flat_map(|&filter_std| {
+                SCORING_SIZE.iter().flat_map(move |&scoring_size| {
+                    THRESHOLD.iter().flat_map(move |&threshold| {
+                        TIME_THRESHOLD.iter().map(move |&time_threshold| {
+                            candidate(filter_std, scoring_size, threshold, time_threshold)
+                        })
+                    })
+                })
+            })
+            .collect(),
+        Search::Random => {
+            let mut random = Random(seed.max(1));
+
+            (0..samples)
+                .map(|_| {
+                    candidate(
+                        random.uniform(FILTER_STD[0], FILTER_STD[FILTER_STD.len() - 1]),
+                        random.uniform(
+                            SCORING_SIZE[0] as f64,
+                            SCORING_SIZE[SCORING_SIZE.len() - 1] as f64,
+                        ) as usize,
+                        random.uniform(THRESHOLD[0], THRESHOLD[THRESHOLD.len() - 1]),
+                        random.uniform(
+                            TIME_THRESHOLD[0] as f64,
+                            TIME_THRESHOLD[TIME_THRESHOLD.len() - 1] as f64,
+                        ) as u64,
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+/// Overall evaluation of `config` on `recordings`
+fn score(
+    config: &StepCounterConfig,
+    recordings: &[&AnnotatedRecording],
+    tolerance: Duration,
+) -> Evaluation {
+    evaluate(
+        recordings.iter().map(|this| {
+            let predicted = step_events_with(config, this.samples.iter().cloned())
+                .into_iter()
+                .map(|this| this.timestamp)
+                .collect();
+
+            (this.name.clone(), predicted, this.steps())
+        }),
+        tolerance,
+    )
+    .pop()
+    .expect("evaluate always returns total")
+}
+
+/// Candidate with the highest F1 on `recordings`
+fn best<'a>(
+    candidates: &'a [StepCounterConfig],
+    recordings: &[&AnnotatedRecording],
+    tolerance: Duration,
+) -> (&'a StepCounterConfig, Evaluation) {
+    candidates
+        .iter()
+        .map(|this| (this, score(this, recordings, tolerance)))
+        .max_by(|a, b| a.1.f1.total_cmp(&b.1.f1))
+        .expect("at least one candidate")
+}
+
+fn subject(recording: &AnnotatedRecording) -> &str {
+    recording.name.split('_').next().unwrap_or(&recording.name)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let Args {
+        input,
+        config,
+        output,
+        search,
+        samples,
+        seed,
+        tolerance,
+        dry,
+    } = <Args as clap::Parser>::parse();
+
+    let base = match config {
+        Some(path) => serde_json::from_slice::<StepCounterConfig>(
+            &std::fs::read(path).map_err(|e| format!("Failed to read config. Reason: {e}"))?,
+        )
+        .map_err(|e| format!("Failed to parse config. Reason: {e}"))?,
+        None => StepCounterConfig::default(),
+    };
+
+    let mut recordings = Vec::new();
+
+    for path in input {
+        match path.is_dir() {
+            true => recordings.extend(AnnotatedRecording::load_dir(path)?),
+            false => recordings.push(AnnotatedRecording::load(path)?),
+        }
+    }
+
+    let tolerance = Duration::from_millis(tolerance);
+    let candidates = candidates(&base, search, samples, seed);
+
+    if candidates.is_empty() {
+        return Err("Nothing to search, number of samples is 0".into());
+    }
+
+    let mut subjects = recordings.iter().map(subject).collect::<Vec<_>>();
+    subjects.sort();
+    subjects.dedup();
+
+    println!(
+        "Search {} candidates on {} recordings of {} subjects",
+        candidates.len(),
+        recordings.len(),
+        subjects.len()
+    );
+
+    // leave-one-subject-out validation shows how tuned parameters work for unseen person
+    if subjects.len() > 1 {
+        for held_out in &subjects {
+            let (train, test) = recordings
+                .iter()
+                .partition::<Vec<_>, _>(|this| subject(this) != *held_out);
+
+            let (config, train_score) = best(&candidates, &train, tolerance);
+            let test_score = score(config, &test, tolerance);
+
+            println!(
+                "Held out {held_out}: train f1 {:.3}, test f1 {:.3} (precision {:.3}, recall {:.3})",
+                train_score.f1, test_score.f1, test_score.precision, test_score.recall
+            );
+        }
+    }
+
+    let all = recordings.iter().collect::<Vec<_>>();
+    let (config, total) = best(&candidates, &all, tolerance);
+
+    println!(
+        "Best on all subjects: f1 {:.3} (precision {:.3}, recall {:.3}), filter_std {:.3}, scoring_size {}, detection_threshold {:.3}, time_threshold {}ms",
+        total.f1,
+        total.precision,
+        total.recall,
+        config.filter_std,
+        config.scoring_size,
+        config.detection_threshold,
+        config.time_threshold.as_millis()
+    );
+
+    if !dry {
+        println!("Save to {}", output.to_string_lossy());
+
+        std::fs::write(
+            output,
+            serde_json::to_string_pretty(config).expect("serde serialization can't fail") + "\n",
+        )
+        .map_err(|e| format!("Failed to save config. Reason: {e}"))?;
+    }
+
+    println!("Done!");
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! Annotated accelerometer recordings e.g. `assets/wrist_25hz/*.csv`.
+//!
+//! CSV has `timestamp,x,y,z,annotation` columns where `annotation`
+//! is number of steps at the sample.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use time::{PrimitiveDateTime, UtcDateTime};
+
+use crate::Accelerometer;
+
+#[derive(Debug, serde::Deserialize)]
+struct AnnotatedCsv {
+    timestamp: PrimitiveDateTime,
+    x: f64,
+    y: f64,
+    z: f64,
+    annotation: usize,
+}
+
+/// Accelerometer recording with annotated steps.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct AnnotatedRecording {
+    /// File name of recording
+    pub name: String,
+    pub samples: Vec<Accelerometer>,
+    /// Number of annotated steps at every sample
+    pub annotations: Vec<usize>,
+}
+
+impl AnnotatedRecording {
+    /// Load recording from CSV file, rows which can't be parsed are skipped.
+    pub fn load(file_path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let file_path = file_path.as_ref();
+
+        let mut reader = csv::Reader::from_path(file_path).map_err(|e| {
+            format!(
+                "Failed to open {}. Reason: {e}",
+                file_path.to_string_lossy()
+            )
+        })?;
+
+        let (samples, annotations) = reader
+            .deserialize::<AnnotatedCsv>()
+            .filter_map(|this| this.ok())
+            .map(
+                |AnnotatedCsv {
+                     timestamp,
+                     x,
+                     y,
+                     z,
+                     annotation,
+                 }| {
+                    let var = timestamp.as_utc() - UtcDateTime::UNIX_EPOCH;
+
+                    (
+                        Accelerometer {
+                            timestamp: Duration::new(
+                                var.whole_seconds() as u64,
+                                var.subsec_nanoseconds() as u32,
+                            ),
+                            x,
+                            y,
+                            z,
+                        },
+                        annotation,
+                    )
+                },
+            )
+            .unzip();
+
+        Ok(Self {
+            name: file_path
+                .file_name()
+                .map(|this| this.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            samples,
+            annotations,
+        })
+    }
+
+    /// Load all CSV files of directory sorted by file name.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Vec<Self>, Box<dyn std::error::Error>> {
+        let dir = dir.as_ref();
+
+        let mut files = std::fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read {}. Reason: {e}", dir.to_string_lossy()))?
+            .filter_map(|this| this.ok().map(|this| this.path()))
+            .filter(|this| this.extension().is_some_and(|this| this == "csv"))
+            .collect::<Vec<PathBuf>>();
+
+        files.sort();
+
+        files.into_iter().map(Self::load).collect()
+    }
+
+    /// Timestamps of annotated steps, sample with `n` steps is repeated `n` times
+    pub fn steps(&self) -> Vec<Duration> {
+        crate::annotated_steps(
+            self.samples
+                .iter()
+                .map(|this| this.timestamp)
+                .zip(self.annotations.iter().copied()),
+        )
+    }
+
+    /// Samples paired with their annotation
+    pub fn labeled(&self) -> Vec<(Accelerometer, usize)> {
+        self.samples
+            .iter()
+            .cloned()
+            .zip(self.annotations.iter().copied())
+            .collect()
+    }
+}
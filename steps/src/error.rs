@@ -0,0 +1,311 @@
+use core::time::Duration;
+
+use crate::{Accelerometer, Gps};
+
+/// Problem with input or config which doesn't allow to count steps.
+///
+/// Returned by `try_` variants of functions, e.g. [`crate::try_steps_count_with`],
+/// which check input instead of panicking or returning misleading `0`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepsError {
+    /// Timestamp of sample at `index` is smaller than timestamp of previous sample
+    UnsortedTimestamps { index: usize },
+    /// Sample at `index` has NaN or infinite value
+    NotFinite { index: usize },
+    /// Input has less samples than algorithm needs
+    TooFewSamples { actual: usize, required: usize },
+    /// Input is shorter than algorithm needs to detect the first step
+    TooShort {
+        actual: Duration,
+        required: Duration,
+    },
+    /// GPS fix at `index` has the same timestamp as previous fix, so speed is infinite
+    ZeroDurationSegment { index: usize },
+    /// Distance from previous GPS fix to fix at `index` didn't converge,
+    /// e.g. Vincenty formula for nearly antipodal points
+    NotConverged { index: usize },
+    /// Parameter of config has value with which algorithm can't work
+    InvalidConfig {
+        parameter: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl core::fmt::Display for StepsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnsortedTimestamps { index } => {
+                write!(f, "Timestamp of sample {index} is before previous sample")
+            }
+            Self::NotFinite { index } => write!(f, "Sample {index} has NaN or infinite value"),
+            Self::TooFewSamples { actual, required } => {
+                write!(
+                    f,
+                    "Input has {actual} samples, at least {required} required"
+                )
+            }
+            Self::TooShort { actual, required } => {
+                write!(f, "Input lasts {actual:?}, at least {required:?} required")
+            }
+            Self::ZeroDurationSegment { index } => {
+                write!(f, "GPS fix {index} has the same timestamp as previous fix")
+            }
+            Self::NotConverged { index } => {
+                write!(
+                    f,
+                    "Distance from previous GPS fix to fix {index} didn't converge"
+                )
+            }
+            Self::InvalidConfig { parameter, reason } => {
+                write!(f, "Invalid `{parameter}`: {reason}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for StepsError {}
+
+/// Check that accelerometer data is sorted, finite and lasts at least `required`.
+pub(crate) fn validate_accelerometer(
+    input: &[Accelerometer],
+    required: Duration,
+) -> Result<(), StepsError> {
+    validate_samples(
+        input
+            .iter()
+            .map(|this| (this.timestamp, [this.x, this.y, this.z])),
+        input.len(),
+    )?;
+
+    let actual = input[input.len() - 1].timestamp - input[0].timestamp;
+
+    if actual < required {
+        return Err(StepsError::TooShort { actual, required });
+    }
+
+    Ok(())
+}
+
+/// Check that GPS data is sorted, finite and has no segments without duration.
+pub(crate) fn validate_gps(input: &[Gps]) -> Result<(), StepsError> {
+    validate_samples(
+        input.iter().map(|this| {
+            (
+                this.timestamp,
+                [
+                    this.latitude,
+                    this.longitude,
+                    this.altitude.unwrap_or_default(),
+                ],
+            )
+        }),
+        input.len(),
+    )?;
+
+    match input
+        .windows(2)
+        .position(|this| this[0].timestamp == this[1].timestamp)
+    {
+        Some(index) => Err(StepsError::ZeroDurationSegment { index: index + 1 }),
+        None => Ok(()),
+    }
+}
+
+fn validate_samples(
+    input: impl Iterator<Item = (Duration, [f64; 3])>,
+    len: usize,
+) -> Result<(), StepsError> {
+    if len < 2 {
+        return Err(StepsError::TooFewSamples {
+            actual: len,
+            required: 2,
+        });
+    }
+
+    let mut previous = None;
+
+    for (index, (timestamp, values)) in input.enumerate() {
+        if values.iter().any(|this| !this.is_finite()) {
+            return Err(StepsError::NotFinite { index });
+        }
+
+        if previous.is_some_and(|previous| timestamp < previous) {
+            return Err(StepsError::UnsortedTimestamps { index });
+        }
+
+        previous = Some(timestamp);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Gps, StepCounter, StepCounterConfig, movement_from_gps, step_events, step_events_with,
+        try_movement_from_gps, try_step_events, try_step_events_with, try_steps_count,
+        try_steps_count_autocorrelation_with, try_steps_from_gps,
+    };
+
+    use super::*;
+
+    fn walking(seconds: u64) -> Vec<Accelerometer> {
+        (0..seconds * 25)
+            .map(|i| {
+                let t = i as f64 / 25.0;
+
+                Accelerometer {
+                    timestamp: Duration::from_millis(i * 40),
+                    x: 0.0,
+                    y: 0.0,
+                    z: 1.0 + 0.3 * (2.0 * std::f64::consts::PI * 2.0 * t).sin(),
+                }
+            })
+            .collect()
+    }
+
+    fn gps(timestamps: &[u64]) -> Vec<Gps> {
+        timestamps
+            .iter()
+            .enumerate()
+            .map(|(i, this)| Gps {
+                timestamp: Duration::from_secs(*this),
+                latitude: 49.2358 + i as f64 * 0.0001,
+                longitude: 28.4858,
+                altitude: None,
+                horizontal_accuracy: None,
+                hdop: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn valid_input() {
+        let config = StepCounterConfig::default();
+        let data = walking(30);
+
+        assert_eq!(
+            try_step_events_with(&config, data.clone()),
+            Ok(step_events_with(&config, data.clone()))
+        );
+        assert_eq!(try_step_events(data.clone()), Ok(step_events(data.clone())));
+        assert!(try_steps_count_autocorrelation_with(&Default::default(), data).is_ok());
+        assert!(try_steps_from_gps(gps(&[0, 10, 20]), 1.8, None).is_ok());
+    }
+
+    #[test]
+    fn accelerometer_errors() {
+        let mut unsorted = walking(30);
+        unsorted.swap(10, 11);
+
+        let mut not_finite = walking(30);
+        not_finite[5].y = f64::NAN;
+
+        assert_eq!(
+            try_steps_count(unsorted),
+            Err(StepsError::UnsortedTimestamps { index: 11 })
+        );
+        assert_eq!(
+            try_steps_count(not_finite),
+            Err(StepsError::NotFinite { index: 5 })
+        );
+        assert_eq!(
+            try_steps_count(walking(0)),
+            Err(StepsError::TooFewSamples {
+                actual: 0,
+                required: 2
+            })
+        );
+        assert!(matches!(
+            try_steps_count(
+                walking(0)
+                    .into_iter()
+                    .chain(walking(1).into_iter().take(10))
+            ),
+            Err(StepsError::TooShort { .. })
+        ));
+        assert!(matches!(
+            try_steps_count_autocorrelation_with(&Default::default(), walking(3)),
+            Err(StepsError::TooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_config() {
+        let config = StepCounterConfig::builder().scoring_size(1).build();
+
+        assert!(matches!(
+            try_step_events_with(&config, walking(30)),
+            Err(StepsError::InvalidConfig {
+                parameter: "scoring_size",
+                ..
+            })
+        ));
+        assert!(StepCounter::try_new(config).is_err());
+        assert!(matches!(
+            StepCounterConfig::builder()
+                .detection_initial_length(usize::MAX)
+                .build()
+                .validate(),
+            Err(StepsError::InvalidConfig {
+                parameter: "detection_initial_length",
+                ..
+            })
+        ));
+        assert!(matches!(
+            StepCounterConfig::builder()
+                .interpolation_time(Duration::MAX)
+                .build()
+                .validate(),
+            Err(StepsError::InvalidConfig { .. })
+        ));
+        assert!(StepCounterConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn gps_errors() {
+        assert_eq!(
+            try_movement_from_gps(gps(&[0, 10, 10, 20])),
+            Err(StepsError::ZeroDurationSegment { index: 2 })
+        );
+        assert_eq!(
+            try_movement_from_gps(gps(&[0, 20, 10])),
+            Err(StepsError::UnsortedTimestamps { index: 2 })
+        );
+        assert!(matches!(
+            try_steps_from_gps(gps(&[0, 10]), 0.0, None),
+            Err(StepsError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn unsorted_input_does_not_panic() {
+        let mut data = walking(30);
+        data.reverse();
+
+        step_events_with(&StepCounterConfig::default(), data);
+        movement_from_gps(gps(&[20, 10, 0]));
+    }
+
+    #[test]
+    fn counter_keeps_state_on_error() {
+        let data = walking(30);
+
+        let mut counter = StepCounter::default();
+        let mut expected = StepCounter::default();
+
+        let mut actual = counter.try_push(data[..300].iter().cloned()).unwrap();
+        actual.extend(
+            counter
+                .try_push(data[..10].iter().cloned())
+                .unwrap_or_default(),
+        );
+        actual.extend(counter.try_push(data[300..].iter().cloned()).unwrap());
+
+        assert_eq!(
+            counter.try_push(data[..10].iter().cloned()),
+            Err(StepsError::UnsortedTimestamps { index: 0 })
+        );
+        assert_eq!(actual, expected.push(data));
+    }
+}
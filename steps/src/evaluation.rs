@@ -0,0 +1,252 @@
+//! Step-level evaluation of step counting.
+//!
+//! Predicted steps are matched to annotated steps one to one if they are
+//! not further than tolerance from each other. Matched steps are true positives,
+//! unmatched predicted steps are false positives and unmatched annotated steps
+//! are false negatives.
+
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
+
+/// Default maximal time between predicted and annotated step to match them
+pub const TOLERANCE: Duration = Duration::from_millis(250);
+
+/// Result of matching predicted steps to annotated steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Matching {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+}
+
+impl Matching {
+    /// Number of predicted steps
+    pub const fn predicted(&self) -> usize {
+        self.true_positives + self.false_positives
+    }
+
+    /// Number of annotated steps
+    pub const fn expected(&self) -> usize {
+        self.true_positives + self.false_negatives
+    }
+
+    /// Part of predicted steps which are real, `1.0` if nothing is predicted
+    pub fn precision(&self) -> f64 {
+        match self.predicted() {
+            0 => 1.0,
+            predicted => self.true_positives as f64 / predicted as f64,
+        }
+    }
+
+    /// Part of annotated steps which are predicted, `1.0` if nothing is annotated
+    pub fn recall(&self) -> f64 {
+        match self.expected() {
+            0 => 1.0,
+            expected => self.true_positives as f64 / expected as f64,
+        }
+    }
+
+    /// Harmonic mean of precision and recall
+    pub fn f1(&self) -> f64 {
+        let (precision, recall) = (self.precision(), self.recall());
+
+        match precision + recall {
+            0.0 => 0.0,
+            sum => 2.0 * precision * recall / sum,
+        }
+    }
+}
+
+impl core::ops::Add for Matching {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            true_positives: self.true_positives + rhs.true_positives,
+            false_positives: self.false_positives + rhs.false_positives,
+            false_negatives: self.false_negatives + rhs.false_negatives,
+        }
+    }
+}
+
+impl core::iter::Sum for Matching {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |sum, this| sum + this)
+    }
+}
+
+/// Timestamps of annotated steps, sample with `n` steps is repeated `n` times
+pub fn annotated_steps(annotated: impl IntoIterator<Item = (Duration, usize)>) -> Vec<Duration> {
+    annotated
+        .into_iter()
+        .flat_map(|(timestamp, annotation)| core::iter::repeat_n(timestamp, annotation))
+        .collect()
+}
+
+/// Match predicted steps to annotated steps within `tolerance`.
+///
+/// Both `predicted` and `expected` must be sorted in asc order.
+pub fn match_steps(predicted: &[Duration], expected: &[Duration], tolerance: Duration) -> Matching {
+    let (mut p, mut e) = (0, 0);
+    let mut true_positives = 0;
+
+    while p < predicted.len() && e < expected.len() {
+        let (predicted, expected) = (predicted[p], expected[e]);
+
+        if predicted.abs_diff(expected) <= tolerance {
+            true_positives += 1;
+            p += 1;
+            e += 1;
+        } else if predicted < expected {
+            p += 1;
+        } else {
+            e += 1;
+        }
+    }
+
+    Matching {
+        true_positives,
+        false_positives: predicted.len() - true_positives,
+        false_negatives: expected.len() - true_positives,
+    }
+}
+
+/// Evaluation of a single recording or of all of them.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Evaluation {
+    pub name: String,
+    pub expected: usize,
+    pub predicted: usize,
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+impl Evaluation {
+    pub fn new(name: impl Into<String>, matching: Matching) -> Self {
+        Self {
+            name: name.into(),
+            expected: matching.expected(),
+            predicted: matching.predicted(),
+            true_positives: matching.true_positives,
+            false_positives: matching.false_positives,
+            false_negatives: matching.false_negatives,
+            precision: matching.precision(),
+            recall: matching.recall(),
+            f1: matching.f1(),
+        }
+    }
+
+    pub fn matching(&self) -> Matching {
+        Matching {
+            true_positives: self.true_positives,
+            false_positives: self.false_positives,
+            false_negatives: self.false_negatives,
+        }
+    }
+}
+
+/// Evaluate every recording and add overall evaluation named `"total"` in the end.
+///
+/// Overall precision, recall and F1 are calculated from summed counts of all recordings.
+///
+/// # Params
+/// - `recordings` - name, predicted and annotated steps of recording
+/// - `tolerance` - see [`match_steps`]
+pub fn evaluate<N: Into<String>>(
+    recordings: impl IntoIterator<Item = (N, Vec<Duration>, Vec<Duration>)>,
+    tolerance: Duration,
+) -> Vec<Evaluation> {
+    let mut evaluations = recordings
+        .into_iter()
+        .map(|(name, predicted, expected)| {
+            Evaluation::new(name, match_steps(&predicted, &expected, tolerance))
+        })
+        .collect::<Vec<_>>();
+
+    let total = evaluations.iter().map(Evaluation::matching).sum();
+    evaluations.push(Evaluation::new("total", total));
+
+    evaluations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn annotated_steps_repeated() {
+        let actual = annotated_steps(millis(&[0, 40, 80, 120]).into_iter().zip([0, 1, 2, 0]));
+
+        assert_eq!(actual, millis(&[40, 80, 80]));
+    }
+
+    #[test]
+    fn matching_within_tolerance() {
+        let predicted = millis(&[100, 600, 1100, 1200, 3000]);
+        let expected = millis(&[0, 500, 1000, 2000]);
+
+        let actual = match_steps(&predicted, &expected, Duration::from_millis(150));
+
+        assert_eq!(
+            actual,
+            Matching {
+                true_positives: 3,
+                false_positives: 2,
+                false_negatives: 1,
+            }
+        );
+        assert_eq!(actual.precision(), 0.6);
+        assert_eq!(actual.recall(), 0.75);
+        assert!((actual.f1() - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn step_matched_once() {
+        let predicted = millis(&[1000]);
+        let expected = millis(&[950, 1050]);
+
+        let actual = match_steps(&predicted, &expected, Duration::from_millis(100));
+
+        assert_eq!(actual.true_positives, 1);
+        assert_eq!(actual.false_negatives, 1);
+    }
+
+    #[test]
+    fn empty() {
+        let actual = match_steps(&[], &[], TOLERANCE);
+
+        assert_eq!(actual.precision(), 1.0);
+        assert_eq!(actual.recall(), 1.0);
+        assert_eq!(actual.f1(), 1.0);
+    }
+
+    #[test]
+    fn total() {
+        let evaluations = evaluate(
+            [
+                ("a", millis(&[0, 500]), millis(&[0, 500])),
+                ("b", millis(&[0]), millis(&[1000])),
+            ],
+            TOLERANCE,
+        );
+
+        assert_eq!(evaluations.len(), 3);
+
+        let total = &evaluations[2];
+
+        assert_eq!(total.name, "total");
+        assert_eq!(total.true_positives, 2);
+        assert_eq!(total.false_positives, 1);
+        assert_eq!(total.false_negatives, 1);
+    }
+}
@@ -0,0 +1,576 @@
+//! Decoder of `record` messages of Garmin FIT activity files.
+//!
+//! Only fields needed by workspace crates are read, other messages and fields
+//! including developer fields are skipped by their definitions.
+
+use std::{collections::HashMap, error::Error, time::Duration};
+
+use activity_duration::ActivityRecord;
+
+use crate::Gps;
+
+/// Seconds between UNIX epoch and FIT epoch 1989-12-31T00:00:00Z
+pub const FIT_EPOCH_SECONDS: u64 = 631_065_600;
+
+/// Global message number of `record`
+const RECORD: u16 = 20;
+
+const TIMESTAMP: u8 = 253;
+const POSITION_LAT: u8 = 0;
+const POSITION_LONG: u8 = 1;
+const ALTITUDE: u8 = 2;
+const HEART_RATE: u8 = 3;
+const CADENCE: u8 = 4;
+const GPS_ACCURACY: u8 = 31;
+const FRACTIONAL_CADENCE: u8 = 53;
+const ENHANCED_ALTITUDE: u8 = 78;
+
+/// Cadence of one `record` message.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cadence {
+    /// UNIX timestamp
+    pub timestamp: Duration,
+    /// Cycles per minute with fractional part.
+    /// For running it's strides, i.e. steps of one foot, so steps per minute are twice more
+    pub rpm: f64,
+}
+
+/// `record` messages of FIT file split into input types of workspace crates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FitActivity {
+    /// Records with position, e.g. for [`crate::steps_from_gps`]
+    pub fixes: Vec<Gps>,
+    /// Records with heart rate, e.g. for `activity_duration::heart_activity`
+    pub heart_rate: Vec<ActivityRecord>,
+    /// Records with cadence
+    pub cadence: Vec<Cadence>,
+}
+
+/// Field of definition message
+#[derive(Debug, Clone, Copy)]
+struct Field {
+    number: u8,
+    size: usize,
+}
+
+/// Definition message of local message type
+#[derive(Debug, Clone)]
+struct Definition {
+    global: u16,
+    big_endian: bool,
+    fields: Vec<Field>,
+    /// Total size of developer fields which are skipped
+    developer_size: usize,
+}
+
+#[derive(Debug, Default)]
+struct Record {
+    timestamp: Option<u32>,
+    latitude: Option<i32>,
+    longitude: Option<i32>,
+    altitude: Option<u16>,
+    enhanced_altitude: Option<u32>,
+    gps_accuracy: Option<u8>,
+    heart_rate: Option<u8>,
+    cadence: Option<u8>,
+    fractional_cadence: Option<u8>,
+}
+
+impl Record {
+    fn finish(self, index: usize, activity: &mut FitActivity) -> Result<(), Box<dyn Error>> {
+        let timestamp = self
+            .timestamp
+            .map(|this| Duration::from_secs(FIT_EPOCH_SECONDS + u64::from(this)))
+            .ok_or_else(|| format!("Failed to decode record {index}. Reason: no timestamp"))?;
+
+        if let (Some(latitude), Some(longitude)) = (self.latitude, self.longitude) {
+            let altitude = match (self.enhanced_altitude, self.altitude) {
+                (Some(altitude), _) => Some(f64::from(altitude)),
+                (None, Some(altitude)) => Some(f64::from(altitude)),
+                (None, None) => None,
+            };
+
+            activity.fixes.push(Gps {
+                timestamp,
+                latitude: semicircles_to_degrees(latitude),
+                longitude: semicircles_to_degrees(longitude),
+                // scale 5, offset 500
+                altitude: altitude.map(|this| this / 5.0 - 500.0),
+                horizontal_accuracy: self.gps_accuracy.map(f64::from),
+                hdop: None,
+            });
+        }
+
+        if let Some(heart_rate) = self.heart_rate {
+            activity.heart_rate.push(ActivityRecord {
+                heart_rate,
+                timestamp,
+            });
+        }
+
+        if let Some(cadence) = self.cadence {
+            activity.cadence.push(Cadence {
+                timestamp,
+                // scale 128
+                rpm: f64::from(cadence)
+                    + self
+                        .fractional_cadence
+                        .map_or(0.0, |this| f64::from(this) / 128.0),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Decode `record` messages of FIT file, chained files are decoded one after another.
+///
+/// Record gives [`Gps`] if it has position, [`ActivityRecord`] if it has heart rate
+/// and [`Cadence`] if it has cadence. Invalid values of fields are treated as missing.
+/// Fails if file is truncated, CRC doesn't match or record has no timestamp.
+///
+/// # Params
+/// - `data` - content of `.fit` file
+pub fn decode_fit(data: &[u8]) -> Result<FitActivity, Box<dyn Error>> {
+    let mut activity = FitActivity::default();
+    let mut records = 0;
+    let mut data = data;
+
+    while !data.is_empty() {
+        let size = decode_file(data, &mut activity, &mut records)?;
+
+        data = &data[size..];
+    }
+
+    Ok(activity)
+}
+
+/// Decode one FIT file from start of `data`, returns its size with CRC
+fn decode_file(
+    data: &[u8],
+    activity: &mut FitActivity,
+    records: &mut usize,
+) -> Result<usize, Box<dyn Error>> {
+    let header_size = usize::from(
+        *data
+            .first()
+            .ok_or("Failed to decode header. Reason: empty")?,
+    );
+
+    if !matches!(header_size, 12 | 14) || data.len() < header_size {
+        return Err(format!("Failed to decode header. Reason: invalid size {header_size}").into());
+    }
+
+    if &data[8..12] != b".FIT" {
+        return Err("Failed to decode header. Reason: no .FIT signature".into());
+    }
+
+    if header_size == 14 {
+        let crc = u16::from_le_bytes([data[12], data[13]]);
+
+        if crc != 0 && crc != fit_crc(&data[..12]) {
+            return Err("Failed to decode header. Reason: CRC doesn't match".into());
+        }
+    }
+
+    let data_size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    let (end, size) = usize::try_from(data_size)
+        .ok()
+        .and_then(|this| this.checked_add(header_size))
+        .and_then(|end| Some((end, end.checked_add(2)?)))
+        .ok_or_else(|| {
+            format!("Failed to decode header. Reason: data size {data_size} is too big")
+        })?;
+
+    if data.len() < size {
+        return Err(format!(
+            "Failed to decode file. Reason: truncated to {} of {size} bytes",
+            data.len()
+        )
+        .into());
+    }
+
+    if u16::from_le_bytes([data[end], data[end + 1]]) != fit_crc(&data[..end]) {
+        return Err("Failed to decode file. Reason: CRC doesn't match".into());
+    }
+
+    // messages end before CRC
+    let data = &data[..end];
+    let mut definitions = HashMap::<u8, Definition>::new();
+    let mut last_timestamp = None::<u32>;
+    let mut position = header_size;
+
+    while position < end {
+        let start = position;
+        let header = data[position];
+        position += 1;
+
+        let truncated = move || format!("Failed to decode message at {start}. Reason: truncated");
+
+        if header & 0xc0 == 0x40 {
+            let local = header & 0x0f;
+            let developer = header & 0x20 != 0;
+
+            let fixed = data.get(position..position + 5).ok_or_else(truncated)?;
+            let big_endian = fixed[1] == 1;
+            let global = match big_endian {
+                true => u16::from_be_bytes([fixed[2], fixed[3]]),
+                false => u16::from_le_bytes([fixed[2], fixed[3]]),
+            };
+            let count = usize::from(fixed[4]);
+            position += 5;
+
+            let fields = data
+                .get(position..position + count * 3)
+                .ok_or_else(truncated)?
+                .chunks_exact(3)
+                .map(|this| Field {
+                    number: this[0],
+                    size: usize::from(this[1]),
+                })
+                .collect();
+            position += count * 3;
+
+            let mut developer_size = 0;
+
+            if developer {
+                let count = usize::from(*data.get(position).ok_or_else(truncated)?);
+                position += 1;
+
+                developer_size = data
+                    .get(position..position + count * 3)
+                    .ok_or_else(truncated)?
+                    .chunks_exact(3)
+                    .map(|this| usize::from(this[1]))
+                    .sum();
+                position += count * 3;
+            }
+
+            definitions.insert(
+                local,
+                Definition {
+                    global,
+                    big_endian,
+                    fields,
+                    developer_size,
+                },
+            );
+
+            continue;
+        }
+
+        // compressed timestamp header keeps local type in 2 bits and offset in 5 bits
+        let (local, offset) = match header & 0x80 != 0 {
+            true => ((header >> 5) & 0x03, Some(u32::from(header & 0x1f))),
+            false => (header & 0x0f, None),
+        };
+
+        let definition = definitions.get(&local).ok_or_else(|| {
+            format!("Failed to decode message at {start}. Reason: undefined local type {local}")
+        })?;
+        let fields = data
+            .get(position..position + definition.size())
+            .ok_or_else(truncated)?;
+
+        let timestamp = match offset {
+            Some(offset) => {
+                let previous = last_timestamp.ok_or_else(|| {
+                    format!("Failed to decode message at {start}. Reason: compressed timestamp without previous")
+                })?;
+
+                let timestamp = (previous & !0x1f) + offset;
+
+                match offset < previous & 0x1f {
+                    true => Some(timestamp.checked_add(0x20).ok_or_else(|| {
+                        format!("Failed to decode message at {start}. Reason: compressed timestamp overflows")
+                    })?),
+                    false => Some(timestamp),
+                }
+            }
+            None => definition.timestamp(fields),
+        };
+
+        last_timestamp = timestamp.or(last_timestamp);
+
+        if definition.global == RECORD {
+            let mut record = definition.record(fields);
+            record.timestamp = record.timestamp.or(offset.and(timestamp));
+            record.finish(*records, activity)?;
+            *records += 1;
+        }
+
+        position += definition.size();
+    }
+
+    Ok(size)
+}
+
+impl Definition {
+    /// Size of data message
+    fn size(&self) -> usize {
+        self.fields.iter().map(|this| this.size).sum::<usize>() + self.developer_size
+    }
+
+    /// Fields with their values, fields of unexpected size are skipped
+    fn values<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = (u8, u64)> + 'a {
+        self.fields
+            .iter()
+            .scan(0, |offset, field| {
+                let bytes = &data[*offset..*offset + field.size];
+                *offset += field.size;
+
+                Some((field.number, bytes))
+            })
+            .filter(|(_, bytes)| matches!(bytes.len(), 1 | 2 | 4))
+            .map(|(number, bytes)| {
+                let value = bytes.iter().enumerate().fold(0, |value, (index, byte)| {
+                    let shift = match self.big_endian {
+                        true => (bytes.len() - 1 - index) * 8,
+                        false => index * 8,
+                    };
+
+                    value | u64::from(*byte) << shift
+                });
+
+                (number, value)
+            })
+    }
+
+    fn timestamp(&self, data: &[u8]) -> Option<u32> {
+        self.values(data)
+            .find(|(number, _)| *number == TIMESTAMP)
+            .and_then(|(_, value)| valid::<u32>(value))
+    }
+
+    fn record(&self, data: &[u8]) -> Record {
+        let mut record = Record::default();
+
+        for (number, value) in self.values(data) {
+            match number {
+                TIMESTAMP => record.timestamp = valid(value),
+                POSITION_LAT => record.latitude = valid_signed(value),
+                POSITION_LONG => record.longitude = valid_signed(value),
+                ALTITUDE => record.altitude = valid(value),
+                ENHANCED_ALTITUDE => record.enhanced_altitude = valid(value),
+                GPS_ACCURACY => record.gps_accuracy = valid(value),
+                HEART_RATE => record.heart_rate = valid(value),
+                CADENCE => record.cadence = valid(value),
+                FRACTIONAL_CADENCE => record.fractional_cadence = valid(value),
+                _ => {}
+            }
+        }
+
+        record
+    }
+}
+
+/// Value of unsigned field, `None` if it doesn't fit type or it's invalid value of all ones
+fn valid<T: TryFrom<u64>>(value: u64) -> Option<T> {
+    let max = T::try_from(value).ok()?;
+
+    match T::try_from(value + 1) {
+        Ok(_) => Some(max),
+        Err(_) => None,
+    }
+}
+
+/// Value of `sint32` field, `None` if it's invalid value `0x7FFFFFFF`
+fn valid_signed(value: u64) -> Option<i32> {
+    u32::try_from(value)
+        .ok()
+        .map(|this| this as i32)
+        .filter(|this| *this != i32::MAX)
+}
+
+fn semicircles_to_degrees(value: i32) -> f64 {
+    f64::from(value) * 180.0 / 2f64.powi(31)
+}
+
+/// CRC-16 of FIT protocol
+fn fit_crc(data: &[u8]) -> u16 {
+    const TABLE: [u16; 16] = [
+        0x0000, 0xcc01, 0xd801, 0x1400, 0xf001, 0x3c00, 0x2800, 0xe401, 0xa001, 0x6c00, 0x7800,
+        0xb401, 0x5000, 0x9c01, 0x8801, 0x4400,
+    ];
+
+    data.iter().fold(0, |crc, byte| {
+        let crc =
+            ((crc >> 4) & 0x0fff) ^ TABLE[usize::from(crc & 0xf)] ^ TABLE[usize::from(byte & 0xf)];
+
+        ((crc >> 4) & 0x0fff) ^ TABLE[usize::from(crc & 0xf)] ^ TABLE[usize::from(byte >> 4)]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use activity_duration::heart_activity;
+
+    use super::*;
+
+    /// 2024-05-01T10:00:00Z in FIT epoch
+    const START: u32 = 1_714_557_600 - FIT_EPOCH_SECONDS as u32;
+
+    /// Wrap messages into FIT file with 14 bytes header and CRC
+    fn file(messages: &[u8]) -> Vec<u8> {
+        let mut data = vec![14, 0x20, 0x08, 0x08];
+        data.extend((messages.len() as u32).to_le_bytes());
+        data.extend(b".FIT");
+        data.extend(fit_crc(&data).to_le_bytes());
+        data.extend(messages);
+        data.extend(fit_crc(&data).to_le_bytes());
+
+        data
+    }
+
+    fn degrees_to_semicircles(value: f64) -> i32 {
+        (value * 2f64.powi(31) / 180.0).round() as i32
+    }
+
+    fn messages() -> Vec<u8> {
+        let mut data = Vec::new();
+
+        // file_id: type activity, skipped
+        data.extend([0x40, 0, 0, 0, 0, 1, 0, 1, 0]);
+        data.extend([0x00, 4]);
+
+        // record: timestamp, lat, long, enhanced_altitude, heart_rate, cadence, fractional_cadence, gps_accuracy
+        data.extend([0x41, 0, 0, RECORD as u8, 0, 8]);
+        data.extend([
+            TIMESTAMP,
+            4,
+            0x86,
+            POSITION_LAT,
+            4,
+            0x85,
+            POSITION_LONG,
+            4,
+            0x85,
+        ]);
+        data.extend([ENHANCED_ALTITUDE, 4, 0x86, HEART_RATE, 1, 2, CADENCE, 1, 2]);
+        data.extend([FRACTIONAL_CADENCE, 1, 2, GPS_ACCURACY, 1, 2]);
+
+        for (second, latitude, bpm, accuracy) in [
+            (0, degrees_to_semicircles(49.2358), 96, 4),
+            (1, degrees_to_semicircles(49.23581), 0xff, 0xff),
+            (2, i32::MAX, 101, 0xff),
+        ] {
+            data.push(0x01);
+            data.extend((START + second).to_le_bytes());
+            data.extend(latitude.to_le_bytes());
+            data.extend(degrees_to_semicircles(28.4858).to_le_bytes());
+            // (251 + 500) * 5
+            data.extend(3755u32.to_le_bytes());
+            data.extend([bpm, 85, 64, accuracy]);
+        }
+
+        // big endian record with developer field and compressed timestamps: heart_rate, cadence
+        data.extend([
+            0x62,
+            0,
+            1,
+            0,
+            RECORD as u8,
+            2,
+            HEART_RATE,
+            1,
+            2,
+            CADENCE,
+            1,
+            2,
+            1,
+            0,
+            2,
+            0,
+        ]);
+
+        for (offset, bpm) in [(6, 104), (35 & 0x1f, 110)] {
+            data.extend([0x80 | (2 << 5) | offset, bpm, 0xff, 0xaa, 0xbb]);
+        }
+
+        data
+    }
+
+    #[test]
+    fn records() {
+        let activity = decode_fit(&file(&messages())).unwrap();
+        let start = Duration::from_secs(1_714_557_600);
+
+        assert_eq!(activity.fixes.len(), 2);
+        assert_eq!(activity.fixes[0].timestamp, start);
+        assert!((activity.fixes[0].latitude - 49.2358).abs() < 1e-7);
+        assert!((activity.fixes[1].latitude - 49.23581).abs() < 1e-7);
+        assert!((activity.fixes[0].longitude - 28.4858).abs() < 1e-7);
+        assert_eq!(activity.fixes[0].altitude, Some(251.0));
+        assert_eq!(activity.fixes[0].horizontal_accuracy, Some(4.0));
+        assert_eq!(activity.fixes[1].horizontal_accuracy, None);
+
+        assert_eq!(
+            activity.heart_rate,
+            [(0, 96), (2, 101), (6, 104), (35, 110)].map(|(second, bpm)| ActivityRecord::from((
+                start + Duration::from_secs(second),
+                bpm
+            )))
+        );
+        assert_eq!(
+            activity.cadence[0],
+            Cadence {
+                timestamp: start,
+                rpm: 85.5
+            }
+        );
+        assert_eq!(activity.cadence.len(), 3);
+
+        let report = heart_activity(activity.heart_rate, 30, 60);
+
+        assert_eq!(report.activity.len(), 3);
+    }
+
+    #[test]
+    fn chained_files() {
+        let mut data = file(&messages());
+        data.extend(file(&messages()));
+
+        let activity = decode_fit(&data).unwrap();
+
+        assert_eq!(activity.fixes.len(), 4);
+        assert_eq!(activity.heart_rate.len(), 8);
+    }
+
+    #[test]
+    fn invalid_files() {
+        let data = file(&messages());
+
+        let mut corrupted = data.clone();
+        corrupted[20] ^= 0xff;
+
+        assert!(decode_fit(&corrupted).is_err());
+        assert!(decode_fit(&data[..data.len() - 3]).is_err());
+        assert!(decode_fit(b"not a fit file").is_err());
+
+        // rollover of compressed timestamp after the last representable one
+        let overflow = [
+            [0x40, 0, 0, RECORD as u8, 0, 1, TIMESTAMP, 4, 0x86, 0x00].as_slice(),
+            &0xffff_fff0u32.to_le_bytes(),
+            &[0x80 | 5, 0xff, 0xff, 0xff, 0xff],
+        ]
+        .concat();
+
+        assert!(
+            decode_fit(&file(&overflow))
+                .unwrap_err()
+                .to_string()
+                .contains("overflows")
+        );
+
+        let mut too_big = file(&messages());
+        too_big[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(decode_fit(&too_big).is_err());
+
+        // record without timestamp
+        let no_timestamp = [0x40, 0, 0, RECORD as u8, 0, 1, HEART_RATE, 1, 2, 0x00, 96];
+
+        assert!(decode_fit(&file(&no_timestamp)).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use super::haversine;
+
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+
+/// Semi-major axis of WGS84 ellipsoid in meters
+pub const WGS84_A: f64 = 6_378_137.0;
+/// Flattening of WGS84 ellipsoid
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// Convergence of longitude on auxiliary sphere in radians, about 0.006mm
+const VINCENTY_TOLERANCE: f64 = 1e-12;
+const VINCENTY_MAX_ITERATIONS: usize = 200;
+
+/// Formula of distance between two coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DistanceFormula {
+    /// Great circle on sphere with radius [`crate::R`], north-south distance near
+    /// the equator is 0.56% longer than on WGS84 ellipsoid
+    #[default]
+    Haversine,
+    /// Vincenty inverse formula on WGS84 ellipsoid, error is below 1mm.
+    /// It doesn't converge for nearly antipodal points, such distance is `None`
+    Vincenty,
+}
+
+impl DistanceFormula {
+    /// Calculates distance from point A to point B in kilometers,
+    /// `None` if formula doesn't converge.
+    ///
+    /// Less accurate formula isn't used instead, so distance keeps accuracy of the formula.
+    pub fn kilometers(
+        self,
+        longitude_1: f64,
+        latitude_1: f64,
+        longitude_2: f64,
+        latitude_2: f64,
+    ) -> Option<f64> {
+        match self {
+            Self::Haversine => Some(haversine(longitude_1, latitude_1, longitude_2, latitude_2)),
+            Self::Vincenty => vincenty(longitude_1, latitude_1, longitude_2, latitude_2),
+        }
+    }
+}
+
+/// Distance along geodesic on WGS84 ellipsoid in kilometers, `None` if it doesn't converge
+fn vincenty(longitude_1: f64, latitude_1: f64, longitude_2: f64, latitude_2: f64) -> Option<f64> {
+    let b = WGS84_A * (1.0 - WGS84_F);
+
+    let l = (longitude_2 - longitude_1).to_radians();
+    // reduced latitudes
+    let u_1 = ((1.0 - WGS84_F) * latitude_1.to_radians().tan()).atan();
+    let u_2 = ((1.0 - WGS84_F) * latitude_2.to_radians().tan()).atan();
+
+    let (sin_u_1, cos_u_1) = (u_1.sin(), u_1.cos());
+    let (sin_u_2, cos_u_2) = (u_2.sin(), u_2.cos());
+
+    let mut lambda = l;
+
+    for _ in 0..VINCENTY_MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = (lambda.sin(), lambda.cos());
+
+        let sin_sigma = ((cos_u_2 * sin_lambda).powi(2)
+            + (cos_u_1 * sin_u_2 - sin_u_1 * cos_u_2 * cos_lambda).powi(2))
+        .sqrt();
+
+        if sin_sigma == 0.0 {
+            // the same point
+            return Some(0.0);
+        }
+
+        let cos_sigma = sin_u_1 * sin_u_2 + cos_u_1 * cos_u_2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+
+        let sin_alpha = cos_u_1 * cos_u_2 * sin_lambda / sin_sigma;
+        let cos_sq_alpha = 1.0 - sin_alpha.powi(2);
+        // equatorial line has cos²α = 0
+        let cos_2_sigma_m = if cos_sq_alpha == 0.0 {
+            0.0
+        } else {
+            cos_sigma - 2.0 * sin_u_1 * sin_u_2 / cos_sq_alpha
+        };
+
+        let c = WGS84_F / 16.0 * cos_sq_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos_sq_alpha));
+        let previous = lambda;
+
+        lambda = l
+            + (1.0 - c)
+                * WGS84_F
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2_sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2_sigma_m.powi(2))));
+
+        if (lambda - previous).abs() < VINCENTY_TOLERANCE {
+            let u_sq = cos_sq_alpha * (WGS84_A.powi(2) - b.powi(2)) / b.powi(2);
+            let a =
+                1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+            let b_coefficient =
+                u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+
+            let delta_sigma = b_coefficient
+                * sin_sigma
+                * (cos_2_sigma_m
+                    + b_coefficient / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2_sigma_m.powi(2))
+                            - b_coefficient / 6.0
+                                * cos_2_sigma_m
+                                * (-3.0 + 4.0 * sin_sigma.powi(2))
+                                * (-3.0 + 4.0 * cos_2_sigma_m.powi(2))));
+
+            return Some(b * a * (sigma - delta_sigma) / 1000.0);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Degrees, minutes and seconds to degrees
+    fn dms(degrees: f64, minutes: f64, seconds: f64) -> f64 {
+        degrees.signum() * (degrees.abs() + minutes / 60.0 + seconds / 3600.0)
+    }
+
+    #[test]
+    fn vincenty_reference() {
+        // Flinders Peak to Buninyong, example of Vincenty's paper
+        let actual = DistanceFormula::Vincenty
+            .kilometers(
+                dms(144.0, 25.0, 29.5244),
+                dms(-37.0, 57.0, 3.7203),
+                dms(143.0, 55.0, 35.3839),
+                dms(-37.0, 39.0, 10.1561),
+            )
+            .unwrap();
+
+        assert!((actual * 1000.0 - 54_972.271).abs() < 0.001, "{actual}");
+    }
+
+    #[test]
+    fn degree_of_latitude() {
+        // length of meridian arc of 1° centered at latitude on WGS84, integrated numerically
+        for (latitude, expected) in [(0.0, 110_574.304), (45.0, 111_131.778), (89.5, 111_693.865)] {
+            let vincenty = DistanceFormula::Vincenty
+                .kilometers(10.0, latitude - 0.5, 10.0, latitude + 0.5)
+                .unwrap();
+            let haversine = DistanceFormula::Haversine
+                .kilometers(10.0, latitude - 0.5, 10.0, latitude + 0.5)
+                .unwrap();
+
+            assert!(
+                (vincenty * 1000.0 - expected).abs() < 0.001,
+                "{latitude} {vincenty}"
+            );
+            assert!((haversine * 1000.0 - expected).abs() / expected < 0.006);
+        }
+    }
+
+    #[test]
+    fn degenerate_points() {
+        assert_eq!(
+            DistanceFormula::Vincenty.kilometers(28.5, 49.2, 28.5, 49.2),
+            Some(0.0)
+        );
+
+        let equator = DistanceFormula::Vincenty
+            .kilometers(0.0, 0.0, 1.0, 0.0)
+            .unwrap();
+
+        assert!((equator * 1000.0 - WGS84_A * 1f64.to_radians()).abs() < 0.001);
+    }
+
+    #[test]
+    fn nearly_antipodal_points() {
+        // Vincenty doesn't converge, haversine would be off by more than 0.5%
+        assert_eq!(
+            DistanceFormula::Vincenty.kilometers(0.0, 0.0, 179.7, 0.5),
+            None
+        );
+        assert!(
+            DistanceFormula::Haversine
+                .kilometers(0.0, 0.0, 179.7, 0.5)
+                .is_some_and(|this| this > 19_900.0)
+        );
+    }
+}
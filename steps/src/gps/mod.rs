@@ -12,54 +12,124 @@
 //! - λ1, φ₁ – First point longitude and latitude coordinates;
 //! - λ2, φ₂ – Second point longitude and latitude coordinates;
 //! - d – Distance between them along Earth's surface.
+//!
+//! Distance along WGS84 ellipsoid is calculated by [`DistanceFormula::Vincenty`].
 
+#[cfg(feature = "fit")]
+mod fit;
+mod geodesic;
 mod models;
+mod outliers;
+mod smoothing;
+#[cfg(feature = "tracks")]
+mod tracks;
+
+use alloc::vec::Vec;
+
+pub use self::{geodesic::*, models::*, outliers::*, smoothing::*};
+
+#[cfg(feature = "fit")]
+pub use self::fit::*;
+#[cfg(feature = "tracks")]
+pub use self::tracks::*;
+
+use crate::{StepsError, validate_gps};
 
-pub use models::*;
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
 
 /// Radius of Earth
 pub const R: f64 = 6371.0087714150598;
 
 const WINDOW_SIZE: usize = 2;
-const SPEED_THRESHOLD_KMPHR: f64 = 20.0;
+/// Speed above which movement isn't counted as walking or running
+pub const SPEED_THRESHOLD_KMPHR: f64 = 20.0;
 
 pub fn movement_from_gps(data: impl IntoIterator<Item = Gps>) -> Vec<Movement> {
+    movement_from_gps_with(DistanceFormula::Haversine, data).expect("haversine always converges")
+}
+
+/// Same as [`movement_from_gps`], but distance is calculated by `formula`,
+/// e.g. [`DistanceFormula::Vincenty`] for course measurement.
+///
+/// Fails with [`StepsError::NotConverged`] if distance between two fixes doesn't converge,
+/// so distance never silently loses accuracy of `formula`.
+///
+/// # Params
+/// - formula - [`DistanceFormula::Haversine`] is the fastest and always converges
+/// - data - gps data which sorted by timestamp in asc order
+pub fn movement_from_gps_with(
+    formula: DistanceFormula,
+    data: impl IntoIterator<Item = Gps>,
+) -> Result<Vec<Movement>, StepsError> {
     let data = data.into_iter().collect::<Vec<_>>();
 
     data.windows(WINDOW_SIZE)
-        .map(|this| {
+        .enumerate()
+        .map(|(index, this)| {
             let first = &this[0];
             let second = &this[1];
 
-            let distance = match (first.altitude, second.altitude) {
-                (Some(altitude_1), Some(altitude_2)) => {
-                    let flat_distance = haversine(
-                        first.longitude,
-                        first.latitude,
-                        second.longitude,
-                        second.latitude,
-                    );
-
-                    (flat_distance.powi(2) + ((altitude_2 - altitude_1) / 1000.0).powi(2)).sqrt()
-                }
-                _ => haversine(
+            let flat_distance = formula
+                .kilometers(
                     first.longitude,
                     first.latitude,
                     second.longitude,
                     second.latitude,
-                ),
+                )
+                .ok_or(StepsError::NotConverged { index: index + 1 })?;
+
+            let distance = match (first.altitude, second.altitude) {
+                (Some(altitude_1), Some(altitude_2)) => {
+                    (flat_distance.powi(2) + ((altitude_2 - altitude_1) / 1000.0).powi(2)).sqrt()
+                }
+                _ => flat_distance,
             };
 
-            Movement {
+            Ok(Movement {
                 distance: Distance::from_kilometers(distance),
-                duration: second.timestamp - first.timestamp,
+                duration: second.timestamp.saturating_sub(first.timestamp),
                 from: Location::from(first),
                 to: Location::from(second),
-            }
+            })
         })
         .collect()
 }
 
+/// Same as [`movement_from_gps`], but fails if there are less than two fixes,
+/// fixes aren't sorted by timestamp, have NaN or infinite coordinates
+/// or two fixes have the same timestamp.
+pub fn try_movement_from_gps(
+    data: impl IntoIterator<Item = Gps>,
+) -> Result<Vec<Movement>, StepsError> {
+    let data = data.into_iter().collect::<Vec<_>>();
+
+    validate_gps(&data)?;
+
+    Ok(movement_from_gps(data))
+}
+
+/// Same as [`steps_from_gps`], but checks data as [`try_movement_from_gps`]
+/// and fails if height isn't positive.
+pub fn try_steps_from_gps(
+    data: impl IntoIterator<Item = Gps>,
+    height: f64,
+    upper_threshold_kmphr: Option<f64>,
+) -> Result<f64, StepsError> {
+    let data = data.into_iter().collect::<Vec<_>>();
+
+    validate_gps(&data)?;
+
+    if height.is_nan() || height <= 0.0 {
+        return Err(StepsError::InvalidConfig {
+            parameter: "height",
+            reason: "must be above zero",
+        });
+    }
+
+    Ok(steps_from_gps(data, height, upper_threshold_kmphr))
+}
+
 /// Calculate number of steps using data from GPS.
 /// This method filters out movement if it was above threshold e.g. bicycling or driving a car.
 ///
@@ -72,12 +142,24 @@ pub fn steps_from_gps(
     height: f64,
     upper_threshold_kmphr: Option<f64>,
 ) -> f64 {
-    let steps_lenght = height * 0.41;
+    steps_from_movements(
+        &movement_from_gps(data),
+        height,
+        upper_threshold_kmphr.unwrap_or(SPEED_THRESHOLD_KMPHR),
+    )
+}
 
-    let upper_threshold_kmphr = upper_threshold_kmphr.unwrap_or(SPEED_THRESHOLD_KMPHR);
+/// Number of steps in `movements` which aren't faster than `upper_threshold_kmphr`,
+/// shared by [`steps_from_gps`] and callers which already have movements.
+pub(crate) fn steps_from_movements(
+    movements: &[Movement],
+    height: f64,
+    upper_threshold_kmphr: f64,
+) -> f64 {
+    let steps_lenght = height * 0.41;
 
-    movement_from_gps(data)
-        .into_iter()
+    movements
+        .iter()
         .filter_map(|this| {
             if upper_threshold_kmphr < this.speed_kmhr() {
                 return None;
@@ -90,12 +172,12 @@ pub fn steps_from_gps(
 
 /// Calculates distance from point A to point B in kilometers
 fn haversine(longitude_1: f64, latitude_1: f64, longitude_2: f64, latitude_2: f64) -> f64 {
-    let d_lat = (std::f64::consts::PI / 180.0) * (latitude_2 - latitude_1);
-    let d_lon = (std::f64::consts::PI / 180.0) * (longitude_2 - longitude_1);
+    let d_lat = (core::f64::consts::PI / 180.0) * (latitude_2 - latitude_1);
+    let d_lon = (core::f64::consts::PI / 180.0) * (longitude_2 - longitude_1);
 
     // convert to radians
-    let latitude_1 = (std::f64::consts::PI / 180.0) * latitude_1;
-    let latitude_2 = (std::f64::consts::PI / 180.0) * latitude_2;
+    let latitude_1 = (core::f64::consts::PI / 180.0) * latitude_1;
+    let latitude_2 = (core::f64::consts::PI / 180.0) * latitude_2;
 
     R * (2.0
         * ((d_lat / 2.0).sin().powi(2)
@@ -146,12 +228,16 @@ mod tests {
                 latitude: 49.235835445219784,
                 longitude: 28.48586563389628,
                 altitude: None,
+                horizontal_accuracy: None,
+                hdop: None,
             },
             Gps {
                 timestamp: std::time::Duration::from_secs(2000),
                 latitude: 49.23297532196681,
                 longitude: 28.493329182275833,
                 altitude: None,
+                horizontal_accuracy: None,
+                hdop: None,
             },
         ];
 
@@ -170,12 +256,16 @@ mod tests {
                 latitude: 49.235835445219784,
                 longitude: 28.48586563389628,
                 altitude: None,
+                horizontal_accuracy: None,
+                hdop: None,
             },
             Gps {
                 timestamp: std::time::Duration::from_secs(1001),
                 latitude: 49.23297532196681,
                 longitude: 28.493329182275833,
                 altitude: None,
+                horizontal_accuracy: None,
+                hdop: None,
             },
         ];
 
@@ -194,12 +284,16 @@ mod tests {
                 latitude: 49.235835445219784,
                 longitude: 28.48586563389628,
                 altitude: Some(500.0),
+                horizontal_accuracy: None,
+                hdop: None,
             },
             Gps {
                 timestamp: std::time::Duration::from_secs(2000),
                 latitude: 49.23297532196681,
                 longitude: 28.493329182275833,
                 altitude: Some(500.0),
+                horizontal_accuracy: None,
+                hdop: None,
             },
         ];
 
@@ -218,12 +312,16 @@ mod tests {
                 latitude: 49.235835445219784,
                 longitude: 28.48586563389628,
                 altitude: Some(500.0),
+                horizontal_accuracy: None,
+                hdop: None,
             },
             Gps {
                 timestamp: std::time::Duration::from_secs(2000),
                 latitude: 49.23297532196681,
                 longitude: 28.493329182275833,
                 altitude: Some(550.0),
+                horizontal_accuracy: None,
+                hdop: None,
             },
         ];
 
@@ -233,4 +331,60 @@ mod tests {
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn movement_vincenty() {
+        let gps = [
+            Gps {
+                timestamp: std::time::Duration::from_secs(1000),
+                latitude: 49.235835445219784,
+                longitude: 28.48586563389628,
+                altitude: Some(500.0),
+                horizontal_accuracy: None,
+                hdop: None,
+            },
+            Gps {
+                timestamp: std::time::Duration::from_secs(2000),
+                latitude: 49.23297532196681,
+                longitude: 28.493329182275833,
+                altitude: Some(550.0),
+                horizontal_accuracy: None,
+                hdop: None,
+            },
+        ];
+
+        let haversine = movement_from_gps_with(DistanceFormula::Haversine, gps.clone()).unwrap();
+        let vincenty = movement_from_gps_with(DistanceFormula::Vincenty, gps.clone()).unwrap();
+
+        assert_eq!(haversine, movement_from_gps(gps));
+
+        let haversine = haversine[0].distance.as_meters();
+        let vincenty = vincenty[0].distance.as_meters();
+
+        assert_ne!(haversine, vincenty);
+        assert!((haversine - vincenty).abs() / vincenty < 0.005);
+    }
+
+    #[test]
+    fn movement_vincenty_not_converged() {
+        // the last fix is nearly antipodal to the previous one
+        let gps = [(0.0, 0.0), (0.001, 0.0), (179.7, 0.5)]
+            .iter()
+            .zip(0..)
+            .map(|((longitude, latitude), i)| Gps {
+                timestamp: std::time::Duration::from_secs(i),
+                latitude: *latitude,
+                longitude: *longitude,
+                altitude: None,
+                horizontal_accuracy: None,
+                hdop: None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            movement_from_gps_with(DistanceFormula::Vincenty, gps.clone()),
+            Err(StepsError::NotConverged { index: 2 })
+        );
+        assert_eq!(movement_from_gps(gps).len(), 2);
+    }
 }
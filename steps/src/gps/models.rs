@@ -1,12 +1,43 @@
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gps {
-    /// UNIX timestamp e.g. duration after [`std::time::Instance::UNIX_EPOCH`]
-    pub timestamp: std::time::Duration,
+    /// UNIX timestamp e.g. duration after [`std::time::SystemTime::UNIX_EPOCH`]
+    pub timestamp: core::time::Duration,
     pub latitude: f64,
     pub longitude: f64,
     /// The altitude of location in meters above the WGS84 reference ellipsoid
     pub altitude: Option<f64>,
+    /// Estimated horizontal accuracy in meters, e.g. radius of 68% confidence on Android
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub horizontal_accuracy: Option<f64>,
+    /// Horizontal dilution of precision reported by receiver e.g. in NMEA `GGA`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hdop: Option<f64>,
+}
+
+impl Gps {
+    /// [`Self::horizontal_accuracy`] and [`Self::hdop`] which are known.
+    ///
+    /// Non-positive or non-finite values are treated as missing,
+    /// e.g. Android reports `0` when accuracy is unknown
+    pub fn known_accuracy(&self) -> (Option<f64>, Option<f64>) {
+        let known = |this: &f64| this.is_finite() && *this > 0.0;
+
+        (
+            self.horizontal_accuracy.filter(known),
+            self.hdop.filter(known),
+        )
+    }
+}
+
+/// Heart rate recorded together with GPS track, e.g. from TCX or GPX extensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeartRate {
+    /// UNIX timestamp, same time base as [`Gps::timestamp`]
+    pub timestamp: core::time::Duration,
+    /// Beats per minute
+    pub bpm: u8,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -35,7 +66,7 @@ impl Distance {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Movement {
     pub distance: Distance,
-    pub duration: std::time::Duration,
+    pub duration: core::time::Duration,
     pub from: Location,
     pub to: Location,
 }
@@ -62,10 +93,10 @@ pub struct Location {
 impl From<&Gps> for Location {
     fn from(
         Gps {
-            timestamp: _,
             latitude,
             longitude,
             altitude,
+            ..
         }: &Gps,
     ) -> Self {
         Self {
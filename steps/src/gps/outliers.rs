@@ -0,0 +1,242 @@
+use alloc::vec::Vec;
+
+use crate::{Gps, SPEED_THRESHOLD_KMPHR};
+
+use super::haversine;
+
+/// Default maximal horizontal accuracy in meters
+pub const MAX_HORIZONTAL_ACCURACY: f64 = 50.0;
+/// Default maximal horizontal dilution of precision
+pub const MAX_HDOP: f64 = 5.0;
+/// Default maximal acceleration in m/s², about 1g which walking or running never reaches
+pub const MAX_ACCELERATION: f64 = 10.0;
+
+/// Parameters of GPS outlier rejection, `None` disables the check.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GpsFilterConfig {
+    /// Fixes with [`Gps::horizontal_accuracy`] above it in meters are dropped
+    pub max_horizontal_accuracy: Option<f64>,
+    /// Fixes with [`Gps::hdop`] above it are dropped
+    pub max_hdop: Option<f64>,
+    /// Fixes which increase speed faster than it in m/s² are dropped
+    pub max_acceleration: Option<f64>,
+    /// Fix is dropped as spike when speed to it and back from it is above it in km/h,
+    /// but speed between its neighbours isn't
+    pub max_spike_speed_kmphr: Option<f64>,
+}
+
+impl Default for GpsFilterConfig {
+    fn default() -> Self {
+        Self {
+            max_horizontal_accuracy: Some(MAX_HORIZONTAL_ACCURACY),
+            max_hdop: Some(MAX_HDOP),
+            max_acceleration: Some(MAX_ACCELERATION),
+            max_spike_speed_kmphr: Some(SPEED_THRESHOLD_KMPHR),
+        }
+    }
+}
+
+impl GpsFilterConfig {
+    /// Unknown accuracy doesn't drop the fix, see [`Gps::known_accuracy`]
+    fn is_accurate(&self, fix: &Gps) -> bool {
+        let below = |value: Option<f64>, max: Option<f64>| match (value, max) {
+            (Some(value), Some(max)) => value <= max,
+            _ => true,
+        };
+
+        let (horizontal_accuracy, hdop) = fix.known_accuracy();
+
+        below(horizontal_accuracy, self.max_horizontal_accuracy) && below(hdop, self.max_hdop)
+    }
+}
+
+/// Drop GPS fixes which would add distance which wasn't traveled.
+///
+/// Checks are done in order:
+/// 1. accuracy - fix has [`Gps::horizontal_accuracy`] or [`Gps::hdop`] above limit,
+///    non-positive or non-finite values are unknown and don't drop the fix
+/// 2. spike - fix jumps away and the next fix returns back, e.g. multipath reflection
+/// 3. acceleration - speed to fix grows faster than [`GpsFilterConfig::max_acceleration`]
+///
+/// Apply before [`crate::movement_from_gps`] or [`crate::steps_from_gps`],
+/// so one bad fix doesn't push its segments above [`SPEED_THRESHOLD_KMPHR`].
+///
+/// # Params
+/// - config - thresholds of checks e.g. [`GpsFilterConfig::default`]
+/// - data - gps data which sorted by timestamp in asc order
+pub fn filter_gps(config: &GpsFilterConfig, data: impl IntoIterator<Item = Gps>) -> Vec<Gps> {
+    let data = data
+        .into_iter()
+        .filter(|this| config.is_accurate(this))
+        .collect::<Vec<_>>();
+
+    let mut output = Vec::<Gps>::with_capacity(data.len());
+
+    for (index, fix) in data.iter().enumerate() {
+        let Some(previous) = output.last() else {
+            output.push(fix.clone());
+            continue;
+        };
+
+        if let (Some(max_speed), Some(next)) = (config.max_spike_speed_kmphr, data.get(index + 1))
+            && speed_kmphr(previous, fix) > max_speed
+            && speed_kmphr(fix, next) > max_speed
+            && speed_kmphr(previous, next) <= max_speed
+        {
+            continue;
+        }
+
+        if let Some(max_acceleration) = config.max_acceleration {
+            let speed = |from: &Gps, to: &Gps| speed_kmphr(from, to) / 3.6;
+
+            let initial = match output.len() {
+                0 | 1 => 0.0,
+                len => speed(&output[len - 2], previous),
+            };
+            let dt = fix
+                .timestamp
+                .saturating_sub(previous.timestamp)
+                .as_secs_f64();
+
+            if (speed(previous, fix) - initial) / dt > max_acceleration {
+                continue;
+            }
+        }
+
+        output.push(fix.clone());
+    }
+
+    output
+}
+
+/// Horizontal speed between two fixes, infinite if they have the same timestamp
+fn speed_kmphr(from: &Gps, to: &Gps) -> f64 {
+    let distance = haversine(from.longitude, from.latitude, to.longitude, to.latitude);
+    let hours = to.timestamp.saturating_sub(from.timestamp).as_secs_f64() / 60.0 / 60.0;
+
+    distance / hours
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{movement_from_gps, steps_from_gps};
+
+    use super::*;
+
+    /// Walk to the north at 1.5 m/s with fix every second
+    fn walk(seconds: u64) -> Vec<Gps> {
+        (0..seconds)
+            .map(|i| Gps {
+                timestamp: Duration::from_secs(i),
+                // 1.5m of latitude
+                latitude: 49.2358 + i as f64 * 1.5 / 111_195.0,
+                longitude: 28.4858,
+                altitude: None,
+                horizontal_accuracy: Some(5.0),
+                hdop: Some(1.0),
+            })
+            .collect()
+    }
+
+    fn distance(data: Vec<Gps>) -> f64 {
+        movement_from_gps(data)
+            .iter()
+            .map(|this| this.distance.as_meters())
+            .sum()
+    }
+
+    #[test]
+    fn clean_track_is_kept() {
+        let data = walk(120);
+
+        assert_eq!(filter_gps(&GpsFilterConfig::default(), data.clone()), data);
+    }
+
+    #[test]
+    fn inaccurate_fixes_are_dropped() {
+        let mut data = walk(60);
+        data[10].horizontal_accuracy = Some(120.0);
+        data[20].hdop = Some(9.0);
+        data[30].horizontal_accuracy = None;
+        data[30].hdop = None;
+
+        let actual = filter_gps(&GpsFilterConfig::default(), data.clone());
+
+        assert_eq!(actual.len(), 58);
+        assert!(!actual.contains(&data[10]) && !actual.contains(&data[20]));
+        assert!(actual.contains(&data[30]));
+    }
+
+    #[test]
+    fn unknown_accuracy_is_kept() {
+        let mut data = walk(60);
+        data[10].horizontal_accuracy = Some(f64::NAN);
+        data[20].horizontal_accuracy = Some(0.0);
+        data[30].horizontal_accuracy = Some(-1.0);
+        data[40].hdop = Some(f64::NAN);
+        data[45].hdop = Some(0.0);
+        data[50].hdop = Some(-1.0);
+
+        let timestamps = |data: &[Gps]| data.iter().map(|this| this.timestamp).collect::<Vec<_>>();
+
+        // NaN isn't equal to itself, so fixes are compared by timestamp
+        let actual = filter_gps(&GpsFilterConfig::default(), data.clone());
+
+        assert_eq!(timestamps(&actual), timestamps(&data));
+
+        // unknown accuracy doesn't hide known one which is above limit
+        data[20].hdop = Some(9.0);
+        data[45].horizontal_accuracy = Some(120.0);
+
+        let actual = timestamps(&filter_gps(&GpsFilterConfig::default(), data.clone()));
+
+        assert_eq!(actual.len(), 58);
+        assert!(!actual.contains(&data[20].timestamp) && !actual.contains(&data[45].timestamp));
+    }
+
+    #[test]
+    fn multipath_spike_is_dropped() {
+        let mut data = walk(120);
+        // 300m to the east and back
+        data[50].longitude += 300.0 / 72_600.0;
+
+        let expected = distance(walk(120));
+
+        assert!(distance(data.clone()) > expected + 500.0);
+
+        let filtered = filter_gps(&GpsFilterConfig::default(), data);
+
+        assert_eq!(filtered.len(), 119);
+        assert!((distance(filtered.clone()) - expected).abs() < 1.0);
+        assert_eq!(
+            steps_from_gps(filtered, 1.8, None),
+            steps_from_gps(walk(120), 1.8, None)
+        );
+    }
+
+    #[test]
+    fn implausible_acceleration_is_dropped() {
+        let mut data = walk(120);
+        // jump which doesn't return, walk continues from the old track
+        data[80].latitude += 100.0 / 111_195.0;
+        data[81].latitude += 100.0 / 111_195.0;
+
+        let config = GpsFilterConfig {
+            max_spike_speed_kmphr: None,
+            ..Default::default()
+        };
+
+        let filtered = filter_gps(&config, data.clone());
+
+        assert!(!filtered.contains(&data[80]));
+        assert!(!filtered.contains(&data[81]));
+        assert_eq!(filtered.len(), 118);
+    }
+}
@@ -0,0 +1,412 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{Gps, Location, R};
+
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+
+/// Default standard deviation of acceleration in m/s², typical for walking
+pub const ACCELERATION_NOISE: f64 = 0.5;
+/// Default standard deviation of position in meters when fix has no accuracy
+pub const MEASUREMENT_NOISE: f64 = 5.0;
+/// User equivalent range error in meters, [`Gps::hdop`] is multiplied by it
+pub const UERE: f64 = 5.0;
+
+/// Standard deviation of initial velocity in m/s
+const INITIAL_VELOCITY_NOISE: f64 = 2.0;
+
+/// Parameters of constant-velocity Kalman filter over GPS track.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct GpsSmoothingConfig {
+    /// Standard deviation of acceleration in m/s², the bigger it is the faster track follows fixes
+    pub acceleration_noise: f64,
+    /// Standard deviation of position in meters for fixes without
+    /// [`Gps::horizontal_accuracy`] and [`Gps::hdop`]
+    pub measurement_noise: f64,
+    /// Run Rauch–Tung–Striebel smoother backwards after filter,
+    /// so every location is corrected by fixes before and after it
+    pub smoother: bool,
+}
+
+impl Default for GpsSmoothingConfig {
+    fn default() -> Self {
+        Self {
+            acceleration_noise: ACCELERATION_NOISE,
+            measurement_noise: MEASUREMENT_NOISE,
+            smoother: true,
+        }
+    }
+}
+
+impl GpsSmoothingConfig {
+    /// Variance of position of `fix` in m², see [`Gps::known_accuracy`]
+    fn variance(&self, fix: &Gps) -> f64 {
+        let (horizontal_accuracy, hdop) = fix.known_accuracy();
+
+        horizontal_accuracy
+            .or(hdop.map(|hdop| hdop * UERE))
+            .unwrap_or(self.measurement_noise)
+            .powi(2)
+    }
+}
+
+/// Position and velocity along one axis with their covariance.
+#[derive(Debug, Clone, Copy)]
+struct State {
+    position: f64,
+    velocity: f64,
+    covariance: [[f64; 2]; 2],
+}
+
+impl State {
+    fn new(position: f64, variance: f64) -> Self {
+        Self {
+            position,
+            velocity: 0.0,
+            covariance: [[variance, 0.0], [0.0, INITIAL_VELOCITY_NOISE.powi(2)]],
+        }
+    }
+
+    /// Move state by `dt` with white noise acceleration of `variance`
+    fn predict(&self, dt: f64, variance: f64) -> Self {
+        let [[a, b], [_, c]] = self.covariance;
+
+        let cross = b + dt * c + variance * dt.powi(3) / 2.0;
+
+        Self {
+            position: self.position + self.velocity * dt,
+            velocity: self.velocity,
+            covariance: [
+                [
+                    a + 2.0 * dt * b + dt.powi(2) * c + variance * dt.powi(4) / 4.0,
+                    cross,
+                ],
+                [cross, c + variance * dt.powi(2)],
+            ],
+        }
+    }
+
+    /// Correct state by measured `position` with `variance`
+    fn update(&self, position: f64, variance: f64) -> Self {
+        let [[a, b], [_, c]] = self.covariance;
+
+        let gain = [a / (a + variance), b / (a + variance)];
+        let innovation = position - self.position;
+
+        Self {
+            position: self.position + gain[0] * innovation,
+            velocity: self.velocity + gain[1] * innovation,
+            covariance: [
+                [(1.0 - gain[0]) * a, (1.0 - gain[0]) * b],
+                [(1.0 - gain[0]) * b, c - gain[1] * b],
+            ],
+        }
+    }
+
+    /// Rauch–Tung–Striebel step: correct filtered state by smoothed `next`,
+    /// `predicted` is this state predicted to time of `next`
+    fn smooth(&self, dt: f64, predicted: &Self, next: &Self) -> Self {
+        let [[a, b], [_, c]] = self.covariance;
+        let [[pa, pb], [_, pc]] = predicted.covariance;
+
+        let determinant = pa * pc - pb * pb;
+
+        if determinant.abs() < f64::EPSILON {
+            return *self;
+        }
+
+        // P·Fᵀ, where F = [[1, dt], [0, 1]]
+        let cross = [[a + dt * b, b], [b + dt * c, c]];
+        let inverse = [
+            [pc / determinant, -pb / determinant],
+            [-pb / determinant, pa / determinant],
+        ];
+        let gain = [
+            [
+                cross[0][0] * inverse[0][0] + cross[0][1] * inverse[1][0],
+                cross[0][0] * inverse[0][1] + cross[0][1] * inverse[1][1],
+            ],
+            [
+                cross[1][0] * inverse[0][0] + cross[1][1] * inverse[1][0],
+                cross[1][0] * inverse[0][1] + cross[1][1] * inverse[1][1],
+            ],
+        ];
+
+        let position = next.position - predicted.position;
+        let velocity = next.velocity - predicted.velocity;
+
+        Self {
+            position: self.position + gain[0][0] * position + gain[0][1] * velocity,
+            velocity: self.velocity + gain[1][0] * position + gain[1][1] * velocity,
+            covariance: self.covariance,
+        }
+    }
+}
+
+/// Local east-north plane with origin at the first fix.
+///
+/// Equirectangular projection, precise enough for tracks of a few kilometers around origin.
+#[derive(Debug, Clone, Copy)]
+struct LocalPlane {
+    latitude: f64,
+    longitude: f64,
+    /// Meters in degree of longitude at latitude of origin
+    east: f64,
+    /// Meters in degree of latitude
+    north: f64,
+}
+
+impl LocalPlane {
+    fn new(origin: &Gps) -> Self {
+        let north = R * 1000.0 * core::f64::consts::PI / 180.0;
+
+        Self {
+            latitude: origin.latitude,
+            longitude: origin.longitude,
+            east: north * (origin.latitude * core::f64::consts::PI / 180.0).cos(),
+            north,
+        }
+    }
+
+    fn project(&self, fix: &Gps) -> [f64; 2] {
+        [
+            (fix.longitude - self.longitude) * self.east,
+            (fix.latitude - self.latitude) * self.north,
+        ]
+    }
+
+    fn unproject(&self, [east, north]: [f64; 2]) -> [f64; 2] {
+        [
+            self.latitude + north / self.north,
+            self.longitude + east / self.east,
+        ]
+    }
+}
+
+/// Smooth GPS track by constant-velocity Kalman filter in local east-north coordinates.
+///
+/// Jitter of fixes adds distance which wasn't traveled, so smoothed track gives
+/// more precise [`crate::movement_from_gps`] at walking speed.
+/// Noise of fix is taken from [`Gps::horizontal_accuracy`], [`Gps::hdop`] multiplied by [`UERE`]
+/// or [`GpsSmoothingConfig::measurement_noise`]. Altitude isn't changed.
+///
+/// Returns location for every fix.
+///
+/// # Params
+/// - config - noise of model e.g. [`GpsSmoothingConfig::default`]
+/// - data - gps data which sorted by timestamp in asc order, e.g. after [`crate::filter_gps`]
+pub fn smooth_gps(
+    config: &GpsSmoothingConfig,
+    data: impl IntoIterator<Item = Gps>,
+) -> Vec<Location> {
+    let data = data.into_iter().collect::<Vec<_>>();
+
+    let Some(first) = data.first() else {
+        return Vec::new();
+    };
+
+    let plane = LocalPlane::new(first);
+    let process = config.acceleration_noise.powi(2);
+
+    let mut filtered = Vec::<[State; 2]>::with_capacity(data.len());
+    let mut predicted = Vec::<[State; 2]>::with_capacity(data.len());
+    let mut previous_timestamp = first.timestamp;
+
+    for fix in &data {
+        let variance = config.variance(fix);
+        let position = plane.project(fix);
+        let dt = dt(previous_timestamp, fix.timestamp);
+
+        previous_timestamp = fix.timestamp;
+
+        let prediction = match filtered.last() {
+            Some(previous) => previous.map(|this: State| this.predict(dt, process)),
+            None => position.map(|this| State::new(this, variance)),
+        };
+
+        filtered.push([
+            prediction[0].update(position[0], variance),
+            prediction[1].update(position[1], variance),
+        ]);
+        predicted.push(prediction);
+    }
+
+    if config.smoother {
+        for index in (0..data.len().saturating_sub(1)).rev() {
+            let dt = dt(data[index].timestamp, data[index + 1].timestamp);
+            let next = filtered[index + 1];
+
+            filtered[index] = [0, 1].map(|axis| {
+                filtered[index][axis].smooth(dt, &predicted[index + 1][axis], &next[axis])
+            });
+        }
+    }
+
+    data.iter()
+        .zip(filtered)
+        .map(|(fix, [east, north])| {
+            let [latitude, longitude] = plane.unproject([east.position, north.position]);
+
+            Location {
+                latitude,
+                longitude,
+                altitude: fix.altitude,
+            }
+        })
+        .collect()
+}
+
+fn dt(from: Duration, to: Duration) -> f64 {
+    to.saturating_sub(from).as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::movement_from_gps;
+
+    use super::*;
+
+    /// Walk to the north-east at 1.4 m/s with fix every second
+    /// and jitter with standard deviation `noise` in meters
+    fn walk(seconds: u64, noise: f64) -> Vec<Gps> {
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+
+            // sum of 4 uniform values is close to normal distribution with variance 1/3
+            (0..4)
+                .map(|i| ((seed >> (i * 16)) & 0xffff) as f64 / 65535.0 - 0.5)
+                .sum::<f64>()
+                * 3.0f64.sqrt()
+        };
+
+        let plane = LocalPlane::new(&Gps {
+            timestamp: Duration::ZERO,
+            latitude: 49.2358,
+            longitude: 28.4858,
+            altitude: None,
+            horizontal_accuracy: None,
+            hdop: None,
+        });
+
+        (0..seconds)
+            .map(|i| {
+                let distance = i as f64 * 1.4 / core::f64::consts::SQRT_2;
+                let [latitude, longitude] =
+                    plane.unproject([distance + noise * random(), distance + noise * random()]);
+
+                Gps {
+                    timestamp: Duration::from_secs(i),
+                    latitude,
+                    longitude,
+                    altitude: Some(200.0),
+                    horizontal_accuracy: Some(5.0),
+                    hdop: None,
+                }
+            })
+            .collect()
+    }
+
+    fn distance(data: &[Gps], locations: Option<Vec<Location>>) -> f64 {
+        let data = match locations {
+            Some(locations) => data
+                .iter()
+                .zip(locations)
+                .map(|(fix, location)| Gps {
+                    latitude: location.latitude,
+                    longitude: location.longitude,
+                    ..fix.clone()
+                })
+                .collect(),
+            None => data.to_vec(),
+        };
+
+        movement_from_gps(data)
+            .iter()
+            .map(|this| this.distance.as_meters())
+            .sum()
+    }
+
+    #[test]
+    fn local_plane_round_trip() {
+        let data = walk(10, 5.0);
+        let plane = LocalPlane::new(&data[0]);
+
+        for fix in &data {
+            let [latitude, longitude] = plane.unproject(plane.project(fix));
+
+            assert!((latitude - fix.latitude).abs() < 1e-9);
+            assert!((longitude - fix.longitude).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn jitter_is_removed() {
+        let data = walk(600, 0.35);
+        let expected = 599.0 * 1.4;
+
+        let raw = distance(&data, None);
+        let filtered = distance(
+            &data,
+            Some(smooth_gps(
+                &GpsSmoothingConfig {
+                    smoother: false,
+                    ..Default::default()
+                },
+                data.clone(),
+            )),
+        );
+        let smoothed = distance(
+            &data,
+            Some(smooth_gps(&GpsSmoothingConfig::default(), data.clone())),
+        );
+
+        assert!(raw > expected * 1.05, "{raw}");
+        assert!((filtered - expected).abs() < expected * 0.02, "{filtered}");
+        assert!((smoothed - expected).abs() < expected * 0.01, "{smoothed}");
+    }
+
+    #[test]
+    fn keeps_length_and_altitude() {
+        let data = walk(30, 5.0);
+
+        let actual = smooth_gps(&GpsSmoothingConfig::default(), data.clone());
+
+        assert_eq!(actual.len(), data.len());
+        assert!(actual.iter().all(|this| this.altitude == Some(200.0)));
+        assert!(smooth_gps(&GpsSmoothingConfig::default(), []).is_empty());
+    }
+
+    #[test]
+    fn unknown_accuracy_is_ignored() {
+        let mut data = walk(30, 5.0);
+
+        for (i, fix) in data.iter_mut().enumerate() {
+            fix.horizontal_accuracy = [Some(0.0), Some(-1.0), Some(f64::NAN)][i % 3];
+            fix.hdop = Some(0.0);
+        }
+
+        let actual = smooth_gps(&GpsSmoothingConfig::default(), data.clone());
+
+        assert!(
+            actual
+                .iter()
+                .all(|this| this.latitude.is_finite() && this.longitude.is_finite())
+        );
+
+        for fix in &mut data {
+            fix.horizontal_accuracy = None;
+            fix.hdop = None;
+        }
+
+        assert_eq!(actual, smooth_gps(&GpsSmoothingConfig::default(), data));
+    }
+}
@@ -0,0 +1,218 @@
+use std::{error::Error, fmt::Write, time::Duration};
+
+use crate::{Gps, HeartRate, Movement};
+
+use super::{
+    Track, TrackPoint, fix_points, format_time, movement_points, parse_points, parse_time,
+    parse_value, with_heart_rate,
+};
+
+/// Parse `<trkpt>` of all tracks and segments of GPX file.
+///
+/// Heart rate is read from `<hr>` of Garmin `TrackPointExtension`.
+/// Fails if XML is malformed or point has no `<time>`.
+pub fn parse_gpx(xml: &str) -> Result<Track, Box<dyn Error>> {
+    parse_points(
+        xml,
+        "trkpt",
+        |element, point| {
+            for (name, value) in [("lat", &mut point.latitude), ("lon", &mut point.longitude)] {
+                let attribute = element
+                    .try_get_attribute(name)
+                    .map_err(|e| format!("Failed to parse {name}. Reason: {e}"))?
+                    .ok_or_else(|| format!("Failed to parse trkpt. Reason: no {name}"))?;
+                let attribute = attribute
+                    .unescape_value()
+                    .map_err(|e| format!("Failed to parse {name}. Reason: {e}"))?;
+
+                *value = Some(parse_value(name, &attribute)?);
+            }
+
+            Ok(())
+        },
+        |elements, text, point| {
+            match elements.last().map(String::as_str) {
+                Some("ele") => point.altitude = Some(parse_value("ele", text)?),
+                Some("time") => point.timestamp = Some(parse_time(text)?),
+                Some("hdop") => point.hdop = Some(parse_value("hdop", text)?),
+                Some("hr") => point.bpm = Some(parse_value("hr", text)?),
+                _ => {}
+            }
+
+            Ok(())
+        },
+    )
+}
+
+/// Write fixes as GPX 1.1 track with heart rate in Garmin `TrackPointExtension`.
+///
+/// # Params
+/// - `fixes` - e.g. after [`crate::filter_gps`], every fix keeps its timestamp
+/// - `heart_rate` - point gets the last sample at or before its time
+pub fn write_gpx(
+    fixes: impl IntoIterator<Item = Gps>,
+    heart_rate: impl IntoIterator<Item = HeartRate>,
+) -> Result<String, Box<dyn Error>> {
+    write_points(with_heart_rate(fix_points(fixes), heart_rate))
+}
+
+/// Write movements as GPX 1.1 track, same as [`write_gpx`].
+///
+/// # Params
+/// - `start` - time of `from` of the first movement, time of every next point adds duration of movement
+/// - `movements` - e.g. from [`crate::movement_from_gps`], `to` of movement is `from` of the next one
+/// - `heart_rate` - point gets the last sample at or before its time
+pub fn write_gpx_movements(
+    start: Duration,
+    movements: &[Movement],
+    heart_rate: impl IntoIterator<Item = HeartRate>,
+) -> Result<String, Box<dyn Error>> {
+    write_points(with_heart_rate(
+        movement_points(start, movements),
+        heart_rate,
+    ))
+}
+
+fn write_points(points: Vec<TrackPoint>) -> Result<String, Box<dyn Error>> {
+    let mut gpx = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<gpx version="1.1" creator="steps" xmlns="http://www.topografix.com/GPX/1/1" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">"#,
+        "\n  <trk>\n    <trkseg>\n",
+    ));
+
+    let mut bpm = None;
+
+    for point in points {
+        bpm = point.bpm.or(bpm);
+
+        // GPX point must have position, heart rate without it goes to the next point
+        let Some(location) = point.location else {
+            continue;
+        };
+
+        writeln!(
+            gpx,
+            r#"      <trkpt lat="{}" lon="{}">"#,
+            location.latitude, location.longitude
+        )?;
+
+        if let Some(altitude) = location.altitude {
+            writeln!(gpx, "        <ele>{altitude}</ele>")?;
+        }
+
+        writeln!(
+            gpx,
+            "        <time>{}</time>",
+            format_time(point.timestamp)?
+        )?;
+
+        if let Some(hdop) = point.hdop {
+            writeln!(gpx, "        <hdop>{hdop}</hdop>")?;
+        }
+
+        if let Some(bpm) = bpm {
+            writeln!(
+                gpx,
+                "        <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>{bpm}</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions>"
+            )?;
+        }
+
+        gpx.push_str("      </trkpt>\n");
+    }
+
+    gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+
+    Ok(gpx)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{super::tests::walk, *};
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="Partner" xmlns="http://www.topografix.com/GPX/1/1" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <metadata><time>2024-05-01T09:00:00Z</time></metadata>
+  <trk>
+    <name>Morning walk</name>
+    <trkseg>
+      <trkpt lat="49.2358" lon="28.4858">
+        <ele>250.5</ele>
+        <time>2024-05-01T10:00:00Z</time>
+        <hdop>1.2</hdop>
+        <extensions>
+          <gpxtpx:TrackPointExtension><gpxtpx:hr>96</gpxtpx:hr></gpxtpx:TrackPointExtension>
+        </extensions>
+      </trkpt>
+      <trkpt lat="49.23582" lon="28.48579"><time>2024-05-01T10:00:01.5Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    #[test]
+    fn parse() {
+        let track = parse_gpx(GPX).unwrap();
+
+        assert_eq!(track.fixes.len(), 2);
+        assert_eq!(track.fixes[0].latitude, 49.2358);
+        assert_eq!(track.fixes[0].altitude, Some(250.5));
+        assert_eq!(track.fixes[0].hdop, Some(1.2));
+        assert_eq!(
+            track.fixes[1].timestamp - track.fixes[0].timestamp,
+            Duration::from_millis(1500)
+        );
+        assert_eq!(track.fixes[1].altitude, None);
+        assert_eq!(
+            track.heart_rate,
+            [HeartRate {
+                timestamp: track.fixes[0].timestamp,
+                bpm: 96
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_gpx(&GPX.replace("<time>2024-05-01T10:00:00Z</time>", "")).is_err());
+        assert!(parse_gpx(&GPX.replace(r#"lat="49.2358""#, r#"lat="north""#)).is_err());
+        assert!(parse_gpx(&GPX.replace("</trkseg>", "")).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let (mut fixes, heart_rate) = walk();
+        fixes[1].hdop = Some(1.2);
+
+        let track = parse_gpx(&write_gpx(fixes.clone(), heart_rate.clone()).unwrap()).unwrap();
+
+        assert_eq!(track.fixes, fixes);
+        assert_eq!(track.heart_rate.len(), fixes.len());
+        assert_eq!(track.heart_rate[2].bpm, heart_rate[1].bpm);
+    }
+
+    #[test]
+    fn single_fix_round_trip() {
+        let (fixes, heart_rate) = walk();
+
+        let gpx = write_gpx(fixes[..1].to_vec(), heart_rate).unwrap();
+        let track = parse_gpx(&gpx).unwrap();
+
+        assert!(gpx.contains("<trkpt"));
+        assert_eq!(track.fixes, fixes[..1]);
+        assert_eq!(track.heart_rate.len(), 1);
+    }
+
+    #[test]
+    fn movements_as_fixes() {
+        let (fixes, heart_rate) = walk();
+
+        let movements = crate::movement_from_gps(fixes.clone());
+
+        assert_eq!(
+            write_gpx_movements(fixes[0].timestamp, &movements, heart_rate.clone()).unwrap(),
+            write_gpx(fixes, heart_rate).unwrap()
+        );
+    }
+}
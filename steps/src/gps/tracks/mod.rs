@@ -0,0 +1,362 @@
+//! Tracks from files of partner apps.
+//!
+//! - [GPX](https://www.topografix.com/gpx.asp) - `<trkpt>` with `<ele>`, `<time>`, `<hdop>`
+//!   and heart rate from Garmin `TrackPointExtension`
+//! - [TCX](https://www8.garmin.com/xmlschemas/TrainingCenterDatabasev2.xsd) - `<Trackpoint>`
+//!   with `<Position>`, `<AltitudeMeters>`, `<Time>` and `<HeartRateBpm>`
+
+mod gpx;
+mod tcx;
+
+use std::{error::Error, str::FromStr, time::Duration};
+
+use quick_xml::{
+    Reader,
+    events::{BytesStart, Event},
+};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+use crate::{Gps, HeartRate, Location, Movement, movement_from_gps};
+
+pub use self::{gpx::*, tcx::*};
+
+/// GPS fixes and heart rate parsed from track file.
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
+pub struct Track {
+    /// Points with position, sorted as in file
+    pub fixes: Vec<Gps>,
+    /// Points with heart rate, some of them may have no position e.g. on treadmill
+    pub heart_rate: Vec<HeartRate>,
+}
+
+/// Values of one point collected from nested elements.
+#[derive(Debug, Default)]
+struct Point {
+    timestamp: Option<Duration>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    altitude: Option<f64>,
+    hdop: Option<f64>,
+    bpm: Option<u8>,
+}
+
+impl Point {
+    fn finish(self, index: usize, track: &mut Track) -> Result<(), Box<dyn Error>> {
+        let timestamp = self
+            .timestamp
+            .ok_or_else(|| format!("Failed to parse point {index}. Reason: point has no time"))?;
+
+        if let (Some(latitude), Some(longitude)) = (self.latitude, self.longitude) {
+            track.fixes.push(Gps {
+                timestamp,
+                latitude,
+                longitude,
+                altitude: self.altitude,
+                horizontal_accuracy: None,
+                hdop: self.hdop,
+            });
+        }
+
+        if let Some(bpm) = self.bpm {
+            track.heart_rate.push(HeartRate { timestamp, bpm });
+        }
+
+        Ok(())
+    }
+}
+
+/// Read every `point` element of `xml` into [`Track`].
+///
+/// # Params
+/// - `on_start` - reads attributes of `point` element
+/// - `on_text` - reads text of element inside of `point`, with names of all open elements
+fn parse_points(
+    xml: &str,
+    point: &str,
+    on_start: impl Fn(&BytesStart<'_>, &mut Point) -> Result<(), Box<dyn Error>>,
+    on_text: impl Fn(&[String], &str, &mut Point) -> Result<(), Box<dyn Error>>,
+) -> Result<Track, Box<dyn Error>> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut track = Track::default();
+    let mut elements = Vec::<String>::new();
+    let mut current = None::<Point>;
+    let mut index = 0;
+
+    loop {
+        let event = reader.read_event().map_err(|e| {
+            format!(
+                "Failed to parse XML at {}. Reason: {e}",
+                reader.buffer_position()
+            )
+        })?;
+
+        match event {
+            Event::Start(this) => {
+                let name = String::from_utf8_lossy(this.local_name().as_ref()).into_owned();
+
+                if name == point {
+                    let mut value = Point::default();
+                    on_start(&this, &mut value)?;
+                    current = Some(value);
+                }
+
+                elements.push(name);
+            }
+            Event::Empty(this) if this.local_name().as_ref() == point.as_bytes() => {
+                let mut value = Point::default();
+                on_start(&this, &mut value)?;
+                value.finish(index, &mut track)?;
+                index += 1;
+            }
+            Event::Text(this) => {
+                if let Some(value) = &mut current {
+                    let text = this.unescape().map_err(|e| {
+                        format!("Failed to parse text of point {index}. Reason: {e}")
+                    })?;
+
+                    on_text(&elements, &text, value)?;
+                }
+            }
+            Event::End(_) => {
+                if elements.pop().as_deref() == Some(point)
+                    && let Some(value) = current.take()
+                {
+                    value.finish(index, &mut track)?;
+                    index += 1;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(track)
+}
+
+/// Point written to track file.
+#[derive(Debug, Clone, PartialEq)]
+struct TrackPoint {
+    timestamp: Duration,
+    /// `None` for heart rate sample without fix at the same time
+    location: Option<Location>,
+    hdop: Option<f64>,
+    /// Distance from start in meters, point without location keeps distance of previous point
+    distance: f64,
+    /// Heart rate sample at the same time
+    bpm: Option<u8>,
+}
+
+/// Point for every fix with its original timestamp and distance from the first fix.
+fn fix_points(fixes: impl IntoIterator<Item = Gps>) -> Vec<TrackPoint> {
+    let fixes = fixes.into_iter().collect::<Vec<_>>();
+
+    let distances = core::iter::once(0.0).chain(
+        movement_from_gps(fixes.iter().cloned())
+            .into_iter()
+            .scan(0.0, |distance, this| {
+                *distance += this.distance.as_meters();
+                Some(*distance)
+            }),
+    );
+
+    fixes
+        .into_iter()
+        .zip(distances)
+        .map(|(fix, distance)| TrackPoint {
+            timestamp: fix.timestamp,
+            location: Some(Location::from(&fix)),
+            hdop: fix.hdop,
+            distance,
+            bpm: None,
+        })
+        .collect()
+}
+
+/// Point at start of the first movement and at end of every movement.
+///
+/// Time of point is `start` plus durations of previous movements, no movements give no points.
+fn movement_points(start: Duration, movements: &[Movement]) -> Vec<TrackPoint> {
+    let Some(first) = movements.first() else {
+        return Vec::new();
+    };
+
+    let point = |timestamp, location: &Location, distance| TrackPoint {
+        timestamp,
+        location: Some(location.clone()),
+        hdop: None,
+        distance,
+        bpm: None,
+    };
+
+    core::iter::once(point(start, &first.from, 0.0))
+        .chain(
+            movements
+                .iter()
+                .scan((start, 0.0), |(timestamp, distance), this| {
+                    *timestamp += this.duration;
+                    *distance += this.distance.as_meters();
+
+                    Some(point(*timestamp, &this.to, *distance))
+                }),
+        )
+        .collect()
+}
+
+/// Merge heart rate into `points` sorted by time.
+///
+/// Sample at time of point is kept in the point, other samples become points without location.
+fn with_heart_rate(
+    points: Vec<TrackPoint>,
+    heart_rate: impl IntoIterator<Item = HeartRate>,
+) -> Vec<TrackPoint> {
+    let mut heart_rate = heart_rate.into_iter().collect::<Vec<_>>();
+    heart_rate.sort();
+
+    let mut merged = Vec::with_capacity(points.len() + heart_rate.len());
+    let mut heart_rate = heart_rate.into_iter().peekable();
+    let mut distance = 0.0;
+
+    let heart_rate_point = |HeartRate { timestamp, bpm }, distance| TrackPoint {
+        timestamp,
+        location: None,
+        hdop: None,
+        distance,
+        bpm: Some(bpm),
+    };
+
+    for mut point in points {
+        while let Some(sample) = heart_rate.next_if(|this| this.timestamp < point.timestamp) {
+            merged.push(heart_rate_point(sample, distance));
+        }
+
+        if let Some(sample) = heart_rate.next_if(|this| this.timestamp == point.timestamp) {
+            point.bpm = Some(sample.bpm);
+        }
+
+        distance = point.distance;
+        merged.push(point);
+    }
+
+    merged.extend(heart_rate.map(|sample| heart_rate_point(sample, distance)));
+
+    merged
+}
+
+fn parse_time(value: &str) -> Result<Duration, Box<dyn Error>> {
+    let time = OffsetDateTime::parse(value, &Rfc3339)
+        .map_err(|e| format!("Failed to parse time {value}. Reason: {e}"))?;
+
+    (time - OffsetDateTime::UNIX_EPOCH)
+        .try_into()
+        .map_err(|e| format!("Failed to parse time {value}. Reason: {e}").into())
+}
+
+fn format_time(timestamp: Duration) -> Result<String, Box<dyn Error>> {
+    let time = time::Duration::try_from(timestamp)
+        .ok()
+        .and_then(|this| OffsetDateTime::UNIX_EPOCH.checked_add(this))
+        .ok_or_else(|| format!("Failed to format time {timestamp:?}. Reason: out of range"))?;
+
+    time.format(&Rfc3339)
+        .map_err(|e| format!("Failed to format time {timestamp:?}. Reason: {e}").into())
+}
+
+fn parse_value<T>(element: &str, value: &str) -> Result<T, Box<dyn Error>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e| format!("Failed to parse {element} {value}. Reason: {e}").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) fn walk() -> (Vec<Gps>, Vec<HeartRate>) {
+        let fixes = (0..5)
+            .map(|i| Gps {
+                timestamp: Duration::from_millis(1_714_557_600_000 + i * 1500),
+                latitude: 49.2358 + i as f64 * 0.00002,
+                longitude: 28.4858 - i as f64 * 0.00001,
+                altitude: Some(250.5 + i as f64),
+                horizontal_accuracy: None,
+                hdop: None,
+            })
+            .collect::<Vec<_>>();
+        let heart_rate = [(0, 96), (2, 101), (5, 104)]
+            .map(|(seconds, bpm)| HeartRate {
+                timestamp: fixes[0].timestamp + Duration::from_secs(seconds),
+                bpm,
+            })
+            .to_vec();
+
+        (fixes, heart_rate)
+    }
+
+    #[test]
+    fn points_of_fixes() {
+        let (fixes, heart_rate) = walk();
+
+        let points = with_heart_rate(fix_points(fixes.clone()), heart_rate.clone());
+
+        assert_eq!(
+            points
+                .iter()
+                .map(|this| (this.timestamp, this.location.is_some(), this.bpm))
+                .collect::<Vec<_>>(),
+            [
+                (fixes[0].timestamp, true, Some(96)),
+                (fixes[1].timestamp, true, None),
+                (heart_rate[1].timestamp, false, Some(101)),
+                (fixes[2].timestamp, true, None),
+                (fixes[3].timestamp, true, None),
+                (heart_rate[2].timestamp, false, Some(104)),
+                (fixes[4].timestamp, true, None),
+            ]
+        );
+        assert_eq!(points[0].distance, 0.0);
+        assert_eq!(points[2].distance, points[1].distance);
+        assert_eq!(
+            points[6].distance,
+            movement_from_gps(fixes.clone())
+                .iter()
+                .map(|this| this.distance.as_meters())
+                .sum::<f64>()
+        );
+        assert!(fix_points([]).is_empty());
+        assert_eq!(
+            with_heart_rate(Vec::new(), heart_rate.clone()).len(),
+            heart_rate.len()
+        );
+    }
+
+    #[test]
+    fn points_of_movements() {
+        let (fixes, _) = walk();
+
+        let actual = movement_points(fixes[0].timestamp, &movement_from_gps(fixes.clone()));
+
+        assert_eq!(actual, fix_points(fixes.clone()));
+        assert!(movement_points(fixes[0].timestamp, &[]).is_empty());
+    }
+
+    #[test]
+    fn time_round_trip() {
+        let timestamp = Duration::new(1_714_557_600, 250_000_000);
+
+        assert_eq!(format_time(timestamp).unwrap(), "2024-05-01T10:00:00.25Z");
+        assert_eq!(parse_time("2024-05-01T10:00:00.25Z").unwrap(), timestamp);
+        assert_eq!(
+            parse_time("2024-05-01T12:00:00+02:00").unwrap(),
+            Duration::from_secs(1_714_557_600)
+        );
+        assert!(parse_time("1969-12-31T23:59:59Z").is_err());
+        assert!(parse_time("yesterday").is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use std::{error::Error, fmt::Write, time::Duration};
+
+use crate::{Gps, HeartRate, Movement};
+
+use super::{
+    Track, TrackPoint, fix_points, format_time, movement_points, parse_points, parse_time,
+    parse_value, with_heart_rate,
+};
+
+/// Parse `<Trackpoint>` of all activities and laps of TCX file.
+///
+/// Points without `<Position>` give only heart rate.
+/// Fails if XML is malformed or point has no `<Time>`.
+pub fn parse_tcx(xml: &str) -> Result<Track, Box<dyn Error>> {
+    parse_points(
+        xml,
+        "Trackpoint",
+        |_, _| Ok(()),
+        |elements, text, point| {
+            let parent = elements
+                .len()
+                .checked_sub(2)
+                .map(|this| elements[this].as_str());
+
+            match (parent, elements.last().map(String::as_str)) {
+                (_, Some("Time")) => point.timestamp = Some(parse_time(text)?),
+                (_, Some("LatitudeDegrees")) => {
+                    point.latitude = Some(parse_value("LatitudeDegrees", text)?)
+                }
+                (_, Some("LongitudeDegrees")) => {
+                    point.longitude = Some(parse_value("LongitudeDegrees", text)?)
+                }
+                (_, Some("AltitudeMeters")) => {
+                    point.altitude = Some(parse_value("AltitudeMeters", text)?)
+                }
+                (Some("HeartRateBpm"), Some("Value")) => {
+                    point.bpm = Some(parse_value("HeartRateBpm", text)?)
+                }
+                _ => {}
+            }
+
+            Ok(())
+        },
+    )
+}
+
+/// Write fixes as TCX activity with one lap and heart rate in `<HeartRateBpm>`.
+///
+/// # Params
+/// - `fixes` - e.g. after [`crate::filter_gps`], every fix keeps its timestamp
+/// - `heart_rate` - sample at time of fix is written in its point,
+///   others are written as points without `<Position>`
+pub fn write_tcx(
+    fixes: impl IntoIterator<Item = Gps>,
+    heart_rate: impl IntoIterator<Item = HeartRate>,
+) -> Result<String, Box<dyn Error>> {
+    write_points(with_heart_rate(fix_points(fixes), heart_rate))
+}
+
+/// Write movements as TCX activity, same as [`write_tcx`].
+///
+/// # Params
+/// - `start` - time of `from` of the first movement, time of every next point adds duration of movement
+/// - `movements` - e.g. from [`crate::movement_from_gps`], `to` of movement is `from` of the next one
+/// - `heart_rate` - sample at time of point is written in it,
+///   others are written as points without `<Position>`
+pub fn write_tcx_movements(
+    start: Duration,
+    movements: &[Movement],
+    heart_rate: impl IntoIterator<Item = HeartRate>,
+) -> Result<String, Box<dyn Error>> {
+    write_points(with_heart_rate(
+        movement_points(start, movements),
+        heart_rate,
+    ))
+}
+
+fn write_points(points: Vec<TrackPoint>) -> Result<String, Box<dyn Error>> {
+    let (start, duration, distance) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (
+            first.timestamp,
+            last.timestamp.saturating_sub(first.timestamp),
+            last.distance,
+        ),
+        _ => (Duration::ZERO, Duration::ZERO, 0.0),
+    };
+    let start_time = format_time(start)?;
+
+    let mut tcx = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">"#,
+        "\n  <Activities>\n",
+        r#"    <Activity Sport="Other">"#,
+        "\n",
+    ));
+
+    writeln!(tcx, "      <Id>{start_time}</Id>")?;
+    writeln!(tcx, r#"      <Lap StartTime="{start_time}">"#)?;
+    writeln!(
+        tcx,
+        "        <TotalTimeSeconds>{}</TotalTimeSeconds>",
+        duration.as_secs_f64()
+    )?;
+    writeln!(tcx, "        <DistanceMeters>{distance}</DistanceMeters>")?;
+    tcx.push_str(concat!(
+        "        <Calories>0</Calories>\n",
+        "        <Intensity>Active</Intensity>\n",
+        "        <TriggerMethod>Manual</TriggerMethod>\n",
+        "        <Track>\n",
+    ));
+
+    for point in points {
+        tcx.push_str("          <Trackpoint>\n");
+        writeln!(
+            tcx,
+            "            <Time>{}</Time>",
+            format_time(point.timestamp)?
+        )?;
+
+        if let Some(location) = point.location {
+            writeln!(
+                tcx,
+                "            <Position><LatitudeDegrees>{}</LatitudeDegrees><LongitudeDegrees>{}</LongitudeDegrees></Position>",
+                location.latitude, location.longitude
+            )?;
+
+            if let Some(altitude) = location.altitude {
+                writeln!(
+                    tcx,
+                    "            <AltitudeMeters>{altitude}</AltitudeMeters>"
+                )?;
+            }
+
+            writeln!(
+                tcx,
+                "            <DistanceMeters>{}</DistanceMeters>",
+                point.distance
+            )?;
+        }
+
+        if let Some(bpm) = point.bpm {
+            writeln!(
+                tcx,
+                "            <HeartRateBpm><Value>{bpm}</Value></HeartRateBpm>"
+            )?;
+        }
+
+        tcx.push_str("          </Trackpoint>\n");
+    }
+
+    tcx.push_str(concat!(
+        "        </Track>\n",
+        "      </Lap>\n",
+        "    </Activity>\n",
+        "  </Activities>\n",
+        "</TrainingCenterDatabase>\n",
+    ));
+
+    Ok(tcx)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::movement_from_gps;
+
+    use super::{super::tests::walk, *};
+
+    const TCX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+  <Activities>
+    <Activity Sport="Running">
+      <Id>2024-05-01T10:00:00Z</Id>
+      <Lap StartTime="2024-05-01T10:00:00Z">
+        <TotalTimeSeconds>2</TotalTimeSeconds>
+        <Track>
+          <Trackpoint>
+            <Time>2024-05-01T10:00:00Z</Time>
+            <Position>
+              <LatitudeDegrees>49.2358</LatitudeDegrees>
+              <LongitudeDegrees>28.4858</LongitudeDegrees>
+            </Position>
+            <AltitudeMeters>250.5</AltitudeMeters>
+            <HeartRateBpm><Value>96</Value></HeartRateBpm>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2024-05-01T10:00:01Z</Time>
+            <HeartRateBpm><Value>98</Value></HeartRateBpm>
+            <Extensions><TPX><Speed>1.5</Speed></TPX></Extensions>
+          </Trackpoint>
+          <Trackpoint>
+            <Time>2024-05-01T10:00:02Z</Time>
+            <Position>
+              <LatitudeDegrees>49.23582</LatitudeDegrees>
+              <LongitudeDegrees>28.48579</LongitudeDegrees>
+            </Position>
+          </Trackpoint>
+        </Track>
+      </Lap>
+    </Activity>
+  </Activities>
+</TrainingCenterDatabase>"#;
+
+    #[test]
+    fn parse() {
+        let track = parse_tcx(TCX).unwrap();
+
+        assert_eq!(track.fixes.len(), 2);
+        assert_eq!(track.fixes[0].longitude, 28.4858);
+        assert_eq!(track.fixes[0].altitude, Some(250.5));
+        assert_eq!(
+            track.fixes[1].timestamp - track.fixes[0].timestamp,
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            track
+                .heart_rate
+                .iter()
+                .map(|this| this.bpm)
+                .collect::<Vec<_>>(),
+            [96, 98]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_tcx(&TCX.replace("<Time>2024-05-01T10:00:01Z</Time>", "")).is_err());
+        assert!(parse_tcx(&TCX.replace("<Value>98</Value>", "<Value>300</Value>")).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let (fixes, heart_rate) = walk();
+
+        let tcx = write_tcx(fixes.clone(), heart_rate.clone()).unwrap();
+        let track = parse_tcx(&tcx).unwrap();
+
+        assert_eq!(track.fixes, fixes);
+        assert_eq!(track.heart_rate, heart_rate);
+        assert!(tcx.contains(&format!(
+            "<DistanceMeters>{}</DistanceMeters>",
+            movement_from_gps(fixes)
+                .iter()
+                .map(|this| this.distance.as_meters())
+                .sum::<f64>()
+        )));
+    }
+
+    #[test]
+    fn single_fix_round_trip() {
+        let (fixes, heart_rate) = walk();
+
+        let tcx = write_tcx(fixes[..1].to_vec(), heart_rate.clone()).unwrap();
+        let track = parse_tcx(&tcx).unwrap();
+
+        assert!(tcx.contains("<TotalTimeSeconds>5</TotalTimeSeconds>"));
+        assert!(tcx.contains("<DistanceMeters>0</DistanceMeters>"));
+        assert_eq!(track.fixes, fixes[..1]);
+        assert_eq!(track.heart_rate, heart_rate);
+    }
+
+    #[test]
+    fn parsed_round_trip() {
+        let track = parse_tcx(TCX).unwrap();
+
+        let actual =
+            parse_tcx(&write_tcx(track.fixes.clone(), track.heart_rate.clone()).unwrap()).unwrap();
+
+        assert_eq!(actual, track);
+    }
+
+    #[test]
+    fn movements_round_trip() {
+        let (fixes, heart_rate) = walk();
+
+        let movements = movement_from_gps(fixes.clone());
+        let tcx = write_tcx_movements(fixes[0].timestamp, &movements, heart_rate.clone()).unwrap();
+
+        assert_eq!(tcx, write_tcx(fixes.clone(), heart_rate.clone()).unwrap());
+        assert_eq!(parse_tcx(&tcx).unwrap(), Track { fixes, heart_rate });
+    }
+}
@@ -0,0 +1,330 @@
+//! Step detection from accelerometer and gyroscope.
+//!
+//! Peaks of acceleration are detected by windowed peak detection and every peak is confirmed
+//! by angular velocity about the swing axis of the forearm. Walking swings the arm while
+//! hand gestures e.g. typing or clapping give acceleration peaks without the swing.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{Accelerometer, StepCounterConfig, StepEvent, step_events_with};
+
+/// Default half of window around the step where swing is searched
+pub const SWING_WINDOW: Duration = Duration::from_millis(250);
+/// Default minimal angular velocity of the swing in rad/s
+pub const MIN_SWING: f64 = 0.5;
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gyroscope {
+    pub timestamp: Duration,
+    /// Angular velocity about x axis in rad/s
+    pub x: f64,
+    /// Angular velocity about y axis in rad/s
+    pub y: f64,
+    /// Angular velocity about z axis in rad/s
+    pub z: f64,
+}
+
+/// Axis of device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Axis {
+    X,
+    /// Across the forearm for most of watches, which is the swing axis of the arm
+    #[default]
+    Y,
+    Z,
+}
+
+/// Accelerometer and gyroscope samples measured at the same time.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImuSample {
+    pub accelerometer: Accelerometer,
+    pub gyroscope: Gyroscope,
+}
+
+impl ImuSample {
+    /// Pair every accelerometer sample with the closest gyroscope sample.
+    ///
+    /// Accelerometer samples without gyroscope sample within `max_offset` are skipped.
+    ///
+    /// # Params
+    /// - `accelerometer` - accelerometer data sorted by timestamp in asc order
+    /// - `gyroscope` - gyroscope data sorted by timestamp in asc order
+    /// - `max_offset` - maximal time between paired samples e.g. half of sampling period
+    pub fn pair(
+        accelerometer: impl IntoIterator<Item = Accelerometer>,
+        gyroscope: &[Gyroscope],
+        max_offset: Duration,
+    ) -> Vec<Self> {
+        let mut next = 0;
+
+        accelerometer
+            .into_iter()
+            .filter_map(|accelerometer| {
+                while next < gyroscope.len() && gyroscope[next].timestamp < accelerometer.timestamp
+                {
+                    next += 1;
+                }
+
+                let closest = [next.checked_sub(1), Some(next)]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|i| gyroscope.get(i))
+                    .min_by_key(|this| this.timestamp.abs_diff(accelerometer.timestamp))?;
+
+                (closest.timestamp.abs_diff(accelerometer.timestamp) <= max_offset).then(|| Self {
+                    gyroscope: closest.clone(),
+                    accelerometer,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Parameters of step detection confirmed by gyroscope.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct ImuConfig {
+    /// Config of peak detection on accelerometer data
+    pub step_counter: StepCounterConfig,
+    /// Axis of gyroscope about which the forearm swings
+    pub swing_axis: Axis,
+    /// Swing is searched in `[step - swing_window, step + swing_window]`
+    pub swing_window: Duration,
+    /// Minimal absolute angular velocity about swing axis in rad/s to confirm the step
+    pub min_swing: f64,
+}
+
+impl Default for ImuConfig {
+    fn default() -> Self {
+        Self {
+            step_counter: StepCounterConfig::default(),
+            swing_axis: Axis::default(),
+            swing_window: SWING_WINDOW,
+            min_swing: MIN_SWING,
+        }
+    }
+}
+
+/// Count steps with peak detection confirmed by gyroscope using [`ImuConfig::default`].
+pub fn steps_count_imu(input: impl IntoIterator<Item = ImuSample>) -> usize {
+    step_events_imu(&ImuConfig::default(), input).len()
+}
+
+/// Detect steps with peak detection confirmed by gyroscope.
+///
+/// Steps are sorted by timestamp in asc order.
+///
+/// # Params
+/// - `config` - config of peak detection and swing confirmation
+/// - `input` - paired samples sorted by timestamp in asc order, see [`ImuSample::pair`]
+pub fn step_events_imu(
+    config: &ImuConfig,
+    input: impl IntoIterator<Item = ImuSample>,
+) -> Vec<StepEvent> {
+    let (accelerometer, swing) = input
+        .into_iter()
+        .map(
+            |ImuSample {
+                 accelerometer,
+                 gyroscope,
+             }| {
+                let swing = match config.swing_axis {
+                    Axis::X => gyroscope.x,
+                    Axis::Y => gyroscope.y,
+                    Axis::Z => gyroscope.z,
+                };
+
+                let timestamp = accelerometer.timestamp;
+
+                (accelerometer, (timestamp, swing.abs()))
+            },
+        )
+        .unzip::<_, _, Vec<_>, Vec<_>>();
+
+    // time goes backwards between joined recordings,
+    // so swing is searched only in the sorted segment of the step
+    let mut segments = Vec::new();
+    let mut begin = 0;
+
+    for i in 1..swing.len() {
+        if swing[i].0 < swing[i - 1].0 {
+            segments.push(begin..i);
+            begin = i;
+        }
+    }
+
+    segments.push(begin..swing.len());
+
+    let mut segment = 0;
+    let mut previous = None;
+
+    step_events_with(&config.step_counter, accelerometer)
+        .into_iter()
+        .filter(|step| {
+            let from = step.timestamp.saturating_sub(config.swing_window);
+            let to = step.timestamp + config.swing_window;
+
+            if previous.is_some_and(|previous| step.timestamp < previous) {
+                segment += 1;
+            }
+
+            previous = Some(step.timestamp);
+
+            // skip segments which end before the step e.g. recordings without steps
+            while segment + 1 < segments.len() && swing[segments[segment].end - 1].0 < from {
+                segment += 1;
+            }
+
+            let Some(swing) = segments.get(segment).map(|this| &swing[this.clone()]) else {
+                return false;
+            };
+
+            swing[swing.partition_point(|(timestamp, _)| *timestamp < from)..]
+                .iter()
+                .take_while(|(timestamp, _)| *timestamp <= to)
+                .any(|(_, swing)| *swing >= config.min_swing)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    /// 25Hz samples, steps every 0.5 seconds and arm swing with `swing` rad/s
+    fn samples(seconds: u64, swing: f64) -> Vec<ImuSample> {
+        (0..seconds * 25)
+            .map(|i| {
+                let timestamp = Duration::from_millis(i * 40);
+                let t = timestamp.as_secs_f64();
+
+                ImuSample {
+                    accelerometer: Accelerometer {
+                        timestamp,
+                        x: 0.0,
+                        y: 0.0,
+                        z: 1.0 + 0.3 * (2.0 * PI * 2.0 * t).sin(),
+                    },
+                    gyroscope: Gyroscope {
+                        timestamp,
+                        x: 0.0,
+                        y: swing * (2.0 * PI * 1.0 * t).sin(),
+                        z: 0.0,
+                    },
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn swing_confirms_steps() {
+        let data = samples(60, 2.0);
+
+        let expected = step_events_with(
+            &StepCounterConfig::default(),
+            data.iter().map(|this| this.accelerometer.clone()),
+        );
+        let actual = step_events_imu(&ImuConfig::default(), data);
+
+        assert!(!expected.is_empty());
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn time_going_backwards() {
+        let config = ImuConfig::default();
+
+        let mut data = samples(60, 2.0);
+        data.extend(samples(60, 2.0));
+
+        let expected = step_events_with(
+            &config.step_counter,
+            data.iter().map(|this| this.accelerometer.clone()),
+        );
+
+        assert!(
+            expected
+                .windows(2)
+                .any(|this| this[1].timestamp < this[0].timestamp)
+        );
+        assert_eq!(step_events_imu(&config, data), expected);
+
+        // swing of the second recording isn't taken from the first one
+        let mut data = samples(60, 0.0);
+        data.extend(samples(60, 2.0));
+
+        let expected = step_events_with(
+            &config.step_counter,
+            samples(60, 2.0).into_iter().map(|this| this.accelerometer),
+        );
+        let actual = step_events_imu(&config, data);
+
+        assert!(!actual.is_empty());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn gestures_without_swing_are_rejected() {
+        let data = samples(60, 0.0);
+
+        assert_eq!(steps_count_imu(data), 0);
+    }
+
+    #[test]
+    fn other_axis_is_ignored() {
+        let data = samples(60, 2.0);
+
+        let config = ImuConfig {
+            swing_axis: Axis::X,
+            ..Default::default()
+        };
+
+        assert!(step_events_imu(&config, data).is_empty());
+    }
+
+    #[test]
+    fn pairing() {
+        let accelerometer = (0..10).map(|i| Accelerometer {
+            timestamp: Duration::from_millis(i * 40),
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        });
+        let gyroscope = (0..4)
+            .map(|i| Gyroscope {
+                timestamp: Duration::from_millis(i * 50 + 5),
+                x: i as f64,
+                y: 0.0,
+                z: 0.0,
+            })
+            .collect::<Vec<_>>();
+
+        let actual = ImuSample::pair(accelerometer, &gyroscope, Duration::from_millis(20));
+
+        let pairs = actual
+            .iter()
+            .map(|this| {
+                (
+                    this.accelerometer.timestamp.as_millis(),
+                    this.gyroscope.timestamp.as_millis(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(pairs, vec![(0, 5), (40, 55), (120, 105), (160, 155)]);
+    }
+}
@@ -1,9 +1,38 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod algorithm;
+mod autocorrelation;
+mod error;
+mod evaluation;
 mod gps;
+mod imu;
+#[cfg(any(not(feature = "std"), test))]
+mod math;
 mod steps;
+mod stride;
+
+pub use self::{
+    algorithm::*, autocorrelation::*, error::*, evaluation::*, gps::*, imu::*, steps::*, stride::*,
+};
+
+pub(crate) use self::error::{validate_accelerometer, validate_gps};
+
+#[cfg(not(any(feature = "std", test)))]
+use self::math::Float;
+
+#[cfg(feature = "dataset")]
+pub use dataset::*;
+
+#[cfg(feature = "dataset")]
+mod dataset;
 
-pub use self::{gps::*, steps::*};
+#[cfg(feature = "ml")]
+pub use machine_counting::*;
 
-// mod machine_counting;
+#[cfg(feature = "ml")]
+mod machine_counting;
 
 /// Simple formula to calculate virtual steps
 ///
@@ -0,0 +1,308 @@
+//! Steps by machine learning.
+//!
+//! Accelerometer data is split into fixed windows of [`StepModel::window_size`] samples.
+//! Every window is described by [`FEATURE_NAMES`] and decision tree predicts
+//! number of steps in the window.
+//!
+//! Model depends on sample rate of data it was trained on, bundled
+//! `assets/machine_counting.json` is trained on wrist accelerometer working in 25Hz.
+
+use std::{path::Path, time::Duration};
+
+use linfa::{
+    Dataset,
+    traits::{Fit, Predict},
+};
+use ndarray::Array2;
+
+use crate::{Accelerometer, autocorrelation::autocorrelation};
+
+/// Default number of samples in window, 2 seconds of 25Hz data
+pub const ML_WINDOW_SIZE: usize = 50;
+
+/// Default maximal depth of decision tree
+pub const ML_MAX_DEPTH: usize = 12;
+
+/// Features extracted from every window
+pub const FEATURE_NAMES: [&str; FEATURE_COUNT] = [
+    "magnitude_mean",
+    "magnitude_std",
+    "magnitude_min",
+    "magnitude_max",
+    "magnitude_crossings",
+    "magnitude_jerk",
+    "x_std",
+    "y_std",
+    "z_std",
+    "period",
+    "period_correlation",
+];
+
+const FEATURE_COUNT: usize = 11;
+
+/// Trained model which predicts number of steps in window.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct StepModel {
+    pub window_size: usize,
+    tree: linfa_trees::DecisionTree<f64, usize>,
+}
+
+impl StepModel {
+    /// Train model on recordings of accelerometer samples and
+    /// number of annotated steps at each sample.
+    ///
+    /// Windows don't cross recordings, incomplete window in the end of recording is dropped.
+    pub fn train(
+        recordings: impl IntoIterator<Item = Vec<(Accelerometer, usize)>>,
+        window_size: usize,
+        max_depth: Option<usize>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if window_size < 2 {
+            return Err("Window should have at least two samples".into());
+        }
+
+        let mut features = Vec::new();
+        let mut targets = Vec::new();
+
+        for recording in recordings {
+            for window in recording.chunks_exact(window_size) {
+                let samples = window
+                    .iter()
+                    .map(|(this, _)| this.clone())
+                    .collect::<Vec<_>>();
+
+                features.extend(window_features(&samples));
+                targets.push(window.iter().map(|(_, steps)| steps).sum::<usize>());
+            }
+        }
+
+        if targets.is_empty() {
+            return Err("Not enough samples to fill a window".into());
+        }
+
+        let features = Array2::from_shape_vec((targets.len(), FEATURE_COUNT), features)
+            .map_err(|e| format!("Failed to init dataset vector. Reason: {e}"))?;
+
+        let train = Dataset::new(features, ndarray::Array1::from_vec(targets))
+            .with_feature_names(FEATURE_NAMES.to_vec());
+
+        let tree = linfa_trees::DecisionTree::params()
+            .max_depth(max_depth)
+            .fit(&train)
+            .map_err(|e| format!("Failed to fit dataset to model. Reason: {e}"))?;
+
+        Ok(Self { window_size, tree })
+    }
+
+    /// Load model saved as JSON
+    pub fn load(model_location: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let model = serde_json::from_reader::<_, Self>(
+            std::fs::File::open(model_location.as_ref())
+                .map_err(|e| format!("Failed to open model. Reason {e}"))?,
+        )
+        .map_err(|e| format!("Failed to init model. Reason {e}"))?;
+
+        Ok(model)
+    }
+
+    /// Predict number of steps in every complete window of `input`.
+    pub fn predict_windows(&self, input: impl IntoIterator<Item = Accelerometer>) -> Vec<usize> {
+        let input = input.into_iter().collect::<Vec<_>>();
+
+        if self.window_size == 0 {
+            return Vec::new();
+        }
+
+        let features = input
+            .chunks_exact(self.window_size)
+            .flat_map(window_features)
+            .collect::<Vec<_>>();
+
+        let windows = features.len() / FEATURE_COUNT;
+
+        if windows == 0 {
+            return Vec::new();
+        }
+
+        let features = Array2::from_shape_vec((windows, FEATURE_COUNT), features)
+            .expect("features have exact shape");
+
+        self.tree.predict(&features).to_vec()
+    }
+
+    /// Predict number of steps in `input`
+    pub fn steps_count(&self, input: impl IntoIterator<Item = Accelerometer>) -> usize {
+        self.predict_windows(input).into_iter().sum()
+    }
+
+    /// Predict timestamps of steps in `input`, e.g. to [`evaluate`](crate::evaluate) model.
+    ///
+    /// Model knows only number of steps in window, so they are spread evenly across it.
+    pub fn step_timestamps(&self, input: impl IntoIterator<Item = Accelerometer>) -> Vec<Duration> {
+        let input = input.into_iter().collect::<Vec<_>>();
+        let predicted = self.predict_windows(input.iter().cloned());
+
+        input
+            .chunks_exact(self.window_size.max(1))
+            .zip(predicted)
+            .flat_map(|(window, steps)| {
+                let start = window[0].timestamp;
+                let length = window[window.len() - 1].timestamp.saturating_sub(start);
+
+                (0..steps).map(move |step| {
+                    start + length.mul_f64((2 * step + 1) as f64 / (2 * steps) as f64)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Count steps with model saved as JSON.
+pub fn steps_count_ml(
+    input: impl IntoIterator<Item = Accelerometer>,
+    model_location: impl AsRef<Path>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    Ok(StepModel::load(model_location)?.steps_count(input))
+}
+
+/// Features of a window in order of [`FEATURE_NAMES`].
+pub fn window_features(window: &[Accelerometer]) -> [f64; FEATURE_COUNT] {
+    let len = window.len().max(1) as f64;
+
+    let magnitude = window
+        .iter()
+        .map(|Accelerometer { x, y, z, .. }| (x.powi(2) + y.powi(2) + z.powi(2)).sqrt())
+        .collect::<Vec<_>>();
+
+    let (mean, std) = mean_std(magnitude.iter().copied());
+    let min = magnitude.iter().copied().fold(f64::MAX, f64::min);
+    let max = magnitude.iter().copied().fold(f64::MIN, f64::max);
+
+    let crossings = magnitude
+        .windows(2)
+        .filter(|this| (this[0] - mean).signum() != (this[1] - mean).signum())
+        .count() as f64
+        / len;
+
+    let jerk = magnitude
+        .windows(2)
+        .map(|this| (this[1] - this[0]).abs())
+        .sum::<f64>()
+        / len;
+
+    let (_, x_std) = mean_std(window.iter().map(|this| this.x));
+    let (_, y_std) = mean_std(window.iter().map(|this| this.y));
+    let (_, z_std) = mean_std(window.iter().map(|this| this.z));
+
+    let centered = magnitude.iter().map(|this| this - mean).collect::<Vec<_>>();
+    let (period, period_correlation) = (2..magnitude.len() / 2)
+        .map(|lag| (lag, autocorrelation(&centered, lag)))
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(lag, correlation)| (lag as f64, correlation))
+        .unwrap_or_default();
+
+    [
+        mean,
+        std,
+        min,
+        max,
+        crossings,
+        jerk,
+        x_std,
+        y_std,
+        z_std,
+        period,
+        period_correlation,
+    ]
+}
+
+fn mean_std(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let len = values.clone().count().max(1) as f64;
+    let mean = values.clone().sum::<f64>() / len;
+    let std = (values.map(|this| (this - mean).powi(2)).sum::<f64>() / len).sqrt();
+
+    (mean, std)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// 25Hz samples with step at every peak of `frequency` wave
+    fn recording(seconds: u32, frequency: f64) -> Vec<(Accelerometer, usize)> {
+        let period = (25.0 / frequency).round() as u32;
+
+        (0..seconds * 25)
+            .map(|i| {
+                let timestamp = Duration::from_millis(u64::from(i) * 40);
+                let t = timestamp.as_secs_f64();
+
+                (
+                    Accelerometer {
+                        timestamp,
+                        x: 0.0,
+                        y: 0.0,
+                        z: 1.0 + 0.3 * (2.0 * std::f64::consts::PI * frequency * t).cos(),
+                    },
+                    usize::from(frequency > 0.0 && i % period == 0),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn train_and_predict() {
+        let recordings = vec![recording(60, 2.0), recording(60, 1.6), recording(60, 0.0)];
+
+        let model = StepModel::train(recordings, ML_WINDOW_SIZE, Some(ML_MAX_DEPTH)).unwrap();
+
+        let walking = model.steps_count(recording(60, 2.0).into_iter().map(|(this, _)| this));
+        let still = model.steps_count(recording(60, 0.0).into_iter().map(|(this, _)| this));
+
+        assert!((110..=130).contains(&walking), "{walking}");
+        assert_eq!(still, 0);
+    }
+
+    #[test]
+    fn train_without_windows() {
+        assert!(StepModel::train([recording(1, 2.0)], ML_WINDOW_SIZE, None).is_err());
+        assert!(StepModel::train([recording(60, 2.0)], 1, None).is_err());
+    }
+
+    #[cfg(feature = "dataset")]
+    #[test]
+    fn bundled_model_on_held_out_subjects() {
+        use crate::{AnnotatedRecording, TOLERANCE, evaluate};
+
+        let bundled = StepModel::load("assets/machine_counting.json").unwrap();
+        let recordings = AnnotatedRecording::load_dir("assets/wrist_25hz").unwrap();
+
+        // bundled model is trained on every subject by `make train_model`, so it is evaluated
+        // by the same training without the subject which is predicted
+        let evaluations = evaluate(
+            recordings.iter().map(|held_out| {
+                let train = recordings
+                    .iter()
+                    .filter(|this| this.name != held_out.name)
+                    .map(AnnotatedRecording::labeled);
+
+                let model =
+                    StepModel::train(train, bundled.window_size, Some(ML_MAX_DEPTH)).unwrap();
+
+                (
+                    held_out.name.clone(),
+                    model.step_timestamps(held_out.samples.iter().cloned()),
+                    held_out.steps(),
+                )
+            }),
+            TOLERANCE,
+        );
+
+        let total = evaluations.last().unwrap();
+
+        // machine_counting.md reports held-out F1 0.551
+        assert!(total.f1 >= 0.54, "{evaluations:?}");
+    }
+}
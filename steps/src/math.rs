@@ -0,0 +1,96 @@
+//! Float math for `no_std` builds.
+//!
+//! `core` doesn't have float functions which need system math library,
+//! so without `std` feature they are implemented by `libm`.
+//! Import [`Float`] only under `not(any(feature = "std", test))`, with `std` inherent methods are used.
+
+/// Float functions of `std` which are missing in `core`.
+pub(crate) trait Float {
+    fn sqrt(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn asin(self) -> Self;
+    fn tan(self) -> Self;
+    fn atan(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn floor(self) -> Self;
+    fn round(self) -> Self;
+}
+
+impl Float for f64 {
+    fn sqrt(self) -> Self {
+        libm::sqrt(self)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        libm::pow(self, f64::from(n))
+    }
+
+    fn powf(self, n: Self) -> Self {
+        libm::pow(self, n)
+    }
+
+    fn sin(self) -> Self {
+        libm::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        libm::cos(self)
+    }
+
+    fn asin(self) -> Self {
+        libm::asin(self)
+    }
+
+    fn tan(self) -> Self {
+        libm::tan(self)
+    }
+
+    fn atan(self) -> Self {
+        libm::atan(self)
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        libm::atan2(self, other)
+    }
+
+    fn floor(self) -> Self {
+        libm::floor(self)
+    }
+
+    fn round(self) -> Self {
+        libm::round(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_as_std() {
+        for x in [-2.5, -0.3, 0.0, 0.25, 0.7, 1.0, 3.9, 1e3] {
+            let close = |a: f64, b: f64| (a - b).abs() <= 1e-12 * a.abs().max(1.0);
+
+            assert!(close(Float::sin(x), x.sin()), "sin {x}");
+            assert!(close(Float::cos(x), x.cos()), "cos {x}");
+            assert!(close(Float::tan(x), x.tan()), "tan {x}");
+            assert!(close(Float::atan(x), x.atan()), "atan {x}");
+            assert!(close(Float::atan2(x, 0.7), x.atan2(0.7)), "atan2 {x}");
+            assert!(close(Float::powi(x, 3), x.powi(3)), "powi {x}");
+            assert!(close(Float::floor(x), x.floor()), "floor {x}");
+            assert!(close(Float::round(x), x.round()), "round {x}");
+
+            if x >= 0.0 {
+                assert!(close(Float::sqrt(x), x.sqrt()), "sqrt {x}");
+                assert!(close(Float::powf(x, 0.25), x.powf(0.25)), "powf {x}");
+            }
+
+            if x.abs() <= 1.0 {
+                assert!(close(Float::asin(x), x.asin()), "asin {x}");
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::steps::StepEvent;
+
+/// Cadence at the moment of the step.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CadencePoint {
+    /// Timestamp of the step which ends the window
+    pub timestamp: Duration,
+    pub steps_per_minute: f64,
+}
+
+/// Cadence of the whole session in steps per minute.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CadenceSummary {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+/// Calculate cadence over rolling window which ends at every step.
+///
+/// Cadence is the number of steps in `(step - window, step]` scaled to minute.
+/// Steps in the beginning are skipped until the first full window is available.
+///
+/// # Params
+/// - `steps` - steps sorted by timestamp in asc order, e.g. from [`crate::step_events`]
+/// - `window` - length of rolling window, e.g. 30 seconds
+pub fn cadence(steps: &[StepEvent], window: Duration) -> Vec<CadencePoint> {
+    let Some(first) = steps.first() else {
+        return Vec::new();
+    };
+
+    if window.is_zero() {
+        return Vec::new();
+    }
+
+    let per_minute = 60.0 / window.as_secs_f64();
+    let mut start = 0;
+
+    steps
+        .iter()
+        .enumerate()
+        .filter_map(|(end, this)| {
+            if this.timestamp.saturating_sub(first.timestamp) < window {
+                return None;
+            }
+
+            while steps[start].timestamp + window <= this.timestamp {
+                start += 1;
+            }
+
+            Some(CadencePoint {
+                timestamp: this.timestamp,
+                steps_per_minute: (end + 1 - start) as f64 * per_minute,
+            })
+        })
+        .collect()
+}
+
+/// Min, max and mean of cadence series, `None` if series is empty.
+pub fn cadence_summary(cadence: &[CadencePoint]) -> Option<CadenceSummary> {
+    if cadence.is_empty() {
+        return None;
+    }
+
+    let (min, max, sum) = cadence
+        .iter()
+        .map(|this| this.steps_per_minute)
+        .fold((f64::MAX, f64::MIN, 0.0), |(min, max, sum), this| {
+            (min.min(this), max.max(this), sum + this)
+        });
+
+    Some(CadenceSummary {
+        min,
+        max,
+        mean: sum / cadence.len() as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn steps(start: Duration, interval: Duration, count: u32) -> Vec<StepEvent> {
+        (0..count)
+            .map(|i| StepEvent {
+                timestamp: start + interval * i,
+                score: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constant_cadence() {
+        let steps = steps(Duration::from_secs(100), Duration::from_millis(500), 240);
+
+        let cadence = cadence(&steps, Duration::from_secs(30));
+
+        assert!(!cadence.is_empty());
+        assert!(cadence.iter().all(|this| this.steps_per_minute == 120.0));
+    }
+
+    #[test]
+    fn summary_of_changing_cadence() {
+        let mut data = steps(Duration::from_secs(0), Duration::from_millis(500), 120);
+        data.extend(steps(
+            Duration::from_secs(60),
+            Duration::from_millis(400),
+            150,
+        ));
+
+        let cadence = cadence(&data, Duration::from_secs(30));
+        let summary = cadence_summary(&cadence).unwrap();
+
+        assert_eq!(summary.min, 120.0);
+        assert_eq!(summary.max, 150.0);
+        assert!(120.0 < summary.mean && summary.mean < 150.0);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(cadence(&[], Duration::from_secs(30)).is_empty());
+        assert_eq!(cadence_summary(&[]), None);
+    }
+}
@@ -0,0 +1,206 @@
+use core::time::Duration;
+
+use crate::{
+    StepsError,
+    steps::{
+        BandPass, DetectionStatistics, Placement, Preprocessing, detection, filtering,
+        intepolation, scoring, time_threshold,
+    },
+};
+
+/// Tuning parameters of the windowed peak detection pipeline.
+///
+/// [`Default`] contains values which were tuned for wrist accelerometer working in 25Hz.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct StepCounterConfig {
+    /// Processing of raw accelerometer data before interpolation
+    pub preprocessing: Preprocessing,
+    /// Time between two points of interpolated signal
+    pub interpolation_time: Duration,
+    /// Maximal time between two points to interpolate between them,
+    /// signal is split into independent segments on bigger gaps, e.g. [`crate::INTERPOLATION_MAX_GAP`].
+    /// `None` interpolates across any gap as before segments were introduced, it is the default
+    pub interpolation_max_gap: Option<Duration>,
+    /// Number of coefficients in gaussian filter
+    pub filter_length: usize,
+    /// Standard deviation of gaussian filter relative to half of its length
+    pub filter_std: f64,
+    /// Number of points in window used to score the midpoint
+    pub scoring_size: usize,
+    /// Number of scored points used to calculate mean and standard deviation of the score
+    pub detection_initial_length: usize,
+    /// How many standard deviations score should be above mean to be detected as peak
+    pub detection_threshold: f64,
+    /// How mean and standard deviation of the score are calculated
+    pub detection_statistics: DetectionStatistics,
+    /// Minimal time between two steps
+    pub time_threshold: Duration,
+}
+
+impl Default for StepCounterConfig {
+    fn default() -> Self {
+        Self {
+            preprocessing: Preprocessing::default(),
+            interpolation_time: intepolation::INTERPOLATION_TIME,
+            interpolation_max_gap: None,
+            filter_length: filtering::FILTER_LENGTH,
+            filter_std: filtering::FILTER_STD,
+            scoring_size: scoring::SCORING_SIZE,
+            detection_initial_length: detection::INITIAL_LENGTH,
+            detection_threshold: detection::THRESHOLD,
+            detection_statistics: DetectionStatistics::Initial,
+            time_threshold: time_threshold::TIME_THRESHOLD,
+        }
+    }
+}
+
+impl StepCounterConfig {
+    pub fn builder() -> StepCounterConfigBuilder {
+        StepCounterConfigBuilder::default()
+    }
+
+    /// Check that values of parameters allow pipeline to work.
+    pub fn validate(&self) -> Result<(), StepsError> {
+        let invalid = |parameter, reason| Err(StepsError::InvalidConfig { parameter, reason });
+
+        if self
+            .preprocessing
+            .gravity_removal
+            .is_some_and(|this| this.is_nan() || this <= 0.0)
+        {
+            return invalid("preprocessing.gravity_removal", "must be above zero");
+        }
+
+        if let Some(BandPass { low_hz, high_hz }) = self.preprocessing.band_pass
+            && (low_hz.is_nan() || high_hz.is_nan() || low_hz <= 0.0 || high_hz <= low_hz)
+        {
+            return invalid(
+                "preprocessing.band_pass",
+                "must be above zero and `low_hz` must be below `high_hz`",
+            );
+        }
+
+        if self.interpolation_time.is_zero() {
+            return invalid("interpolation_time", "must be above zero");
+        }
+
+        if self.filter_length < 2 {
+            return invalid("filter_length", "must be at least 2");
+        }
+
+        if self.filter_std.is_nan() || self.filter_std <= 0.0 {
+            return invalid("filter_std", "must be above zero");
+        }
+
+        if self.scoring_size < 3 {
+            return invalid("scoring_size", "must be at least 3");
+        }
+
+        if !self.detection_threshold.is_finite() {
+            return invalid("detection_threshold", "must be finite");
+        }
+
+        if self.min_duration().is_none() {
+            return invalid(
+                "detection_initial_length",
+                "together with `filter_length` and `scoring_size` must fit in `Duration`",
+            );
+        }
+
+        match self.detection_statistics {
+            DetectionStatistics::Rolling { window: 0 } => {
+                invalid("detection_statistics.window", "must be above zero")
+            }
+            DetectionStatistics::Exponential { alpha } if !(alpha > 0.0 && alpha <= 1.0) => {
+                invalid("detection_statistics.alpha", "must be in (0, 1]")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Shortest input in which the first step could be detected,
+    /// `None` if it doesn't fit in [`Duration`]
+    pub fn min_duration(&self) -> Option<Duration> {
+        let points = self
+            .filter_length
+            .checked_add(self.scoring_size)?
+            .checked_add(self.detection_initial_length)?;
+
+        self.interpolation_time
+            .checked_mul(u32::try_from(points).ok()?)
+    }
+
+    /// Builder which starts from profile of `placement` instead of default values
+    pub fn builder_for(placement: Placement) -> StepCounterConfigBuilder {
+        StepCounterConfigBuilder {
+            config: placement.config(),
+        }
+    }
+}
+
+/// Builder for [`StepCounterConfig`] which starts from default values.
+#[derive(Debug, Clone, Default)]
+pub struct StepCounterConfigBuilder {
+    config: StepCounterConfig,
+}
+
+impl StepCounterConfigBuilder {
+    pub fn preprocessing(mut self, preprocessing: Preprocessing) -> Self {
+        self.config.preprocessing = preprocessing;
+        self
+    }
+
+    pub fn interpolation_time(mut self, interpolation_time: Duration) -> Self {
+        self.config.interpolation_time = interpolation_time;
+        self
+    }
+
+    pub fn interpolation_max_gap(mut self, interpolation_max_gap: Option<Duration>) -> Self {
+        self.config.interpolation_max_gap = interpolation_max_gap;
+        self
+    }
+
+    pub fn filter_length(mut self, filter_length: usize) -> Self {
+        self.config.filter_length = filter_length;
+        self
+    }
+
+    pub fn filter_std(mut self, filter_std: f64) -> Self {
+        self.config.filter_std = filter_std;
+        self
+    }
+
+    pub fn scoring_size(mut self, scoring_size: usize) -> Self {
+        self.config.scoring_size = scoring_size;
+        self
+    }
+
+    pub fn detection_initial_length(mut self, detection_initial_length: usize) -> Self {
+        self.config.detection_initial_length = detection_initial_length;
+        self
+    }
+
+    pub fn detection_threshold(mut self, detection_threshold: f64) -> Self {
+        self.config.detection_threshold = detection_threshold;
+        self
+    }
+
+    pub fn detection_statistics(mut self, detection_statistics: DetectionStatistics) -> Self {
+        self.config.detection_statistics = detection_statistics;
+        self
+    }
+
+    pub fn time_threshold(mut self, time_threshold: Duration) -> Self {
+        self.config.time_threshold = time_threshold;
+        self
+    }
+
+    pub fn build(self) -> StepCounterConfig {
+        self.config
+    }
+}
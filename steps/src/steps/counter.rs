@@ -0,0 +1,154 @@
+use alloc::vec::Vec;
+
+use crate::{
+    StepsError,
+    steps::{
+        Accelerometer, DataPoint, Scalar, StepCounterConfig, StepEvent, detection::Detection,
+        filtering::Filtering, intepolation::Interpolation, preprocessing::Preprocessor,
+        scoring::Scoring, time_threshold::TimeThreshold,
+    },
+};
+
+/// Stateful windowed peak detection for real-time use.
+///
+/// Accepts accelerometer data in chunks of any size and carries state
+/// of every stage across chunk boundaries, so steps detected from chunks
+/// are the same as [`crate::step_events_with`] for the whole recording.
+///
+/// Step is emitted as soon as it is confirmed which is delayed by
+/// half of filter and scoring windows.
+///
+/// Signal is processed in scalar type `T` of pushed samples, see [`Scalar`].
+#[derive(Debug, Clone)]
+pub struct StepCounter<T: Scalar = f64> {
+    config: StepCounterConfig,
+    preprocessing: Preprocessor<T>,
+    interpolation: Interpolation<T>,
+    filtering: Filtering<T>,
+    scoring: Scoring<T>,
+    detection: Detection<T>,
+    time_threshold: TimeThreshold<T>,
+    count: usize,
+    interpolated: Vec<DataPoint<T>>,
+    /// Timestamp of the last pushed sample
+    last: Option<core::time::Duration>,
+}
+
+impl Default for StepCounter {
+    fn default() -> Self {
+        Self::new(StepCounterConfig::default())
+    }
+}
+
+impl StepCounter {
+    pub fn new(config: StepCounterConfig) -> Self {
+        Self::with_scalar(config)
+    }
+
+    /// Same as [`Self::new`], but fails if config is invalid, see [`StepCounterConfig::validate`]
+    pub fn try_new(config: StepCounterConfig) -> Result<Self, StepsError> {
+        config.validate()?;
+
+        Ok(Self::new(config))
+    }
+}
+
+impl<T: Scalar> StepCounter<T> {
+    /// Counter which processes signal in `T` instead of `f64`, e.g. `StepCounter::<Q16>::with_scalar`
+    pub fn with_scalar(config: StepCounterConfig) -> Self {
+        Self {
+            preprocessing: Preprocessor::new(&config),
+            interpolation: Interpolation::new(&config),
+            filtering: Filtering::new(&config),
+            scoring: Scoring::new(&config),
+            detection: Detection::new(&config),
+            time_threshold: TimeThreshold::new(&config),
+            config,
+            count: 0,
+            interpolated: Vec::new(),
+            last: None,
+        }
+    }
+
+    pub fn config(&self) -> &StepCounterConfig {
+        &self.config
+    }
+
+    /// Total number of steps confirmed since creation or last [`Self::reset`]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Process next chunk of data which is sorted by timestamp in asc order
+    /// and goes after previous chunk.
+    ///
+    /// Returns steps confirmed by this chunk.
+    pub fn push(&mut self, chunk: impl IntoIterator<Item = Accelerometer<T>>) -> Vec<StepEvent> {
+        let mut output = Vec::new();
+
+        for this in chunk {
+            self.last = Some(this.timestamp);
+
+            if self
+                .interpolation
+                .push(self.preprocessing.push(this), &mut self.interpolated)
+            {
+                // windows must not span across the gap
+                self.filtering = Filtering::new(&self.config);
+                self.scoring = Scoring::new(&self.config);
+            }
+
+            for point in self.interpolated.drain(..) {
+                let step = self
+                    .filtering
+                    .push(point)
+                    .and_then(|this| self.scoring.push(this))
+                    .and_then(|this| self.detection.push(this))
+                    .and_then(|this| self.time_threshold.push(this));
+
+                if let Some(step) = step {
+                    output.push(StepEvent::from(step));
+                }
+            }
+        }
+
+        self.count += output.len();
+
+        output
+    }
+
+    /// Drop state of all stages e.g. when new recording starts
+    pub fn reset(&mut self) {
+        *self = Self::with_scalar(self.config.clone());
+    }
+}
+
+impl StepCounter {
+    /// Same as [`Self::push`], but fails without changing state if chunk isn't sorted
+    /// by timestamp, goes before previous chunk or has NaN or infinite axes.
+    pub fn try_push(
+        &mut self,
+        chunk: impl IntoIterator<Item = Accelerometer>,
+    ) -> Result<Vec<StepEvent>, StepsError> {
+        let chunk = chunk.into_iter().collect::<Vec<_>>();
+
+        let mut previous = self.last;
+
+        for (index, this) in chunk.iter().enumerate() {
+            if [this.x, this.y, this.z]
+                .iter()
+                .any(|this| !this.is_finite())
+            {
+                return Err(StepsError::NotFinite { index });
+            }
+
+            if previous.is_some_and(|previous| this.timestamp < previous) {
+                return Err(StepsError::UnsortedTimestamps { index });
+            }
+
+            previous = Some(this.timestamp);
+        }
+
+        Ok(self.push(chunk))
+    }
+}
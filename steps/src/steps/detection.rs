@@ -1,49 +1,274 @@
-use crate::steps::DataPoint;
+use alloc::{collections::VecDeque, vec::Vec};
 
-const INITIAL_LENGTH: usize = 15;
-const THRESHOLD: f64 = 1.2;
+use crate::steps::{DataPoint, Scalar, StepCounterConfig};
 
-pub fn detection(input: impl IntoIterator<Item = DataPoint>) -> Vec<DataPoint> {
-    let input = input.into_iter().collect::<Vec<_>>();
+pub(crate) const INITIAL_LENGTH: usize = 15;
+pub(crate) const THRESHOLD: f64 = 1.2;
 
-    let mut count = 0;
-    let mut mean = 0.0;
-    let mut std = 0.0;
+/// How mean and standard deviation of score are calculated for detection threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DetectionStatistics {
+    /// Calculated from the first [`StepCounterConfig::detection_initial_length`] points
+    /// and frozen for the rest of recording
+    #[default]
+    Initial,
+    /// Calculated over sliding window of last `window` scored points
+    Rolling { window: usize },
+    /// Exponentially weighted with smoothing factor `alpha` in `(0, 1]`,
+    /// the bigger it is the faster statistics follow the signal
+    Exponential { alpha: f64 },
+}
 
-    input
-        .iter()
-        .take(INITIAL_LENGTH)
-        .enumerate()
-        .for_each(|(index, this)| {
-            let o_mean = mean;
-            count += 1;
-            match index {
-                1 => {
-                    mean = this.magnitude;
+#[derive(Debug, Clone)]
+enum Statistics<T> {
+    Initial {
+        count: usize,
+        mean: T,
+        std: T,
+    },
+    Rolling {
+        size: usize,
+        window: VecDeque<T>,
+        sum: T,
+        sum_squares: T,
+        /// Values removed since sums were recomputed from the window
+        removed: usize,
+    },
+    Exponential {
+        alpha: T,
+        mean: Option<T>,
+        variance: T,
+    },
+}
+
+impl<T: Scalar> Statistics<T> {
+    fn new(kind: DetectionStatistics) -> Self {
+        match kind {
+            DetectionStatistics::Initial => Self::Initial {
+                count: 0,
+                mean: T::ZERO,
+                std: T::ZERO,
+            },
+            DetectionStatistics::Rolling { window } => Self::Rolling {
+                size: window,
+                window: VecDeque::with_capacity(window + 1),
+                sum: T::ZERO,
+                sum_squares: T::ZERO,
+                removed: 0,
+            },
+            DetectionStatistics::Exponential { alpha } => Self::Exponential {
+                alpha: T::from_f64(alpha),
+                mean: None,
+                variance: T::ZERO,
+            },
+        }
+    }
+
+    /// Add new value, `index` is number of values added before
+    fn update(&mut self, index: usize, value: T) {
+        let two = T::ONE + T::ONE;
+
+        match self {
+            Self::Initial { count, mean, std } => {
+                let o_mean = *mean;
+                *count += 1;
+                let count = T::from_usize(*count);
+
+                match index {
+                    1 => {
+                        *mean = value;
+                    }
+                    2 => {
+                        *mean = (*mean + value) / two;
+                        *std = (square(value - *mean) + square(o_mean - *mean)).sqrt() / two;
+                    }
+                    _ => {
+                        *mean = (value + (count - T::ONE) * *mean) / count;
+                        *std = ((count - two) * square(*std) / (count - T::ONE)
+                            + square(o_mean - *mean)
+                            + square(value - *mean))
+                        .sqrt();
+                    }
                 }
-                2 => {
-                    mean = (mean + this.magnitude) / 2.0;
-                    std = ((this.magnitude - mean).powi(2) + (o_mean - mean).powi(2)).sqrt() / 2.0;
+            }
+            Self::Rolling {
+                size,
+                window,
+                sum,
+                sum_squares,
+                removed,
+            } => {
+                window.push_back(value);
+                *sum = *sum + value;
+                *sum_squares = *sum_squares + square(value);
+
+                if window.len() > *size
+                    && let Some(old) = window.pop_front()
+                {
+                    *sum = *sum - old;
+                    *sum_squares = *sum_squares - square(old);
+                    *removed += 1;
                 }
-                _ => {
-                    mean = (this.magnitude + (f64::from(count) - 1.0) * mean) / f64::from(count);
-                    std = ((f64::from(count) - 2.0) * std.powi(2) / (f64::from(count) - 1.0)
-                        + (o_mean - mean).powi(2)
-                        + (this.magnitude - mean).powi(2))
-                    .sqrt();
+
+                // add-then-subtract accumulates rounding error of f32 and Q16,
+                // so sums are recomputed once every window
+                if *removed >= *size {
+                    *sum = window.iter().fold(T::ZERO, |sum, this| sum + *this);
+                    *sum_squares = window.iter().fold(T::ZERO, |sum, this| sum + square(*this));
+                    *removed = 0;
                 }
             }
-        });
+            Self::Exponential {
+                alpha,
+                mean,
+                variance,
+            } => match mean {
+                None => *mean = Some(value),
+                Some(mean) => {
+                    let diff = value - *mean;
+                    *mean = *mean + *alpha * diff;
+                    *variance = (T::ONE - *alpha) * (*variance + *alpha * square(diff));
+                }
+            },
+        }
+    }
 
-    input
-        .into_iter()
-        .skip(INITIAL_LENGTH)
-        .filter_map(|this| {
-            if (this.magnitude - mean) > std * THRESHOLD {
-                return Some(this);
+    /// Current mean and standard deviation
+    fn get(&self) -> (T, T) {
+        match self {
+            Self::Initial { mean, std, .. } => (*mean, *std),
+            Self::Rolling {
+                window,
+                sum,
+                sum_squares,
+                ..
+            } => {
+                if window.is_empty() {
+                    return (T::ZERO, T::ZERO);
+                }
+
+                let len = T::from_usize(window.len());
+                let mean = *sum / len;
+                let variance = *sum_squares / len - square(mean);
+
+                match variance < T::ZERO {
+                    true => (mean, T::ZERO),
+                    false => (mean, variance.sqrt()),
+                }
             }
+            Self::Exponential { mean, variance, .. } => (mean.unwrap_or_default(), variance.sqrt()),
+        }
+    }
+
+    /// Whether statistics keep updating after initial points
+    fn is_adaptive(&self) -> bool {
+        !matches!(self, Self::Initial { .. })
+    }
+}
+
+fn square<T: Scalar>(value: T) -> T {
+    value * value
+}
+
+/// Keeps points which score is at least [`StepCounterConfig::detection_threshold`]
+/// standard deviations above mean score.
+///
+/// The first [`StepCounterConfig::detection_initial_length`] points
+/// are used only to warm up statistics.
+///
+/// Statistics are kept in [`Scalar::Accumulator`] of `T`.
+#[derive(Debug, Clone)]
+pub(crate) struct Detection<T: Scalar = f64> {
+    initial_length: usize,
+    threshold: T::Accumulator,
+    index: usize,
+    statistics: Statistics<T::Accumulator>,
+}
+
+impl<T: Scalar> Detection<T> {
+    pub fn new(config: &StepCounterConfig) -> Self {
+        Self {
+            initial_length: config.detection_initial_length,
+            threshold: T::Accumulator::from_f64(config.detection_threshold),
+            index: 0,
+            statistics: Statistics::new(config.detection_statistics),
+        }
+    }
+
+    pub fn push(&mut self, point: DataPoint<T>) -> Option<DataPoint<T>> {
+        let index = self.index;
+        self.index += 1;
+
+        let score = T::Accumulator::from(point.magnitude);
+
+        if index < self.initial_length {
+            self.statistics.update(index, score);
+            return None;
+        }
+
+        let (mean, std) = self.statistics.get();
+
+        if self.statistics.is_adaptive() {
+            self.statistics.update(index, score);
+        }
+
+        if (score - mean) > std * self.threshold {
+            return Some(point);
+        }
+
+        None
+    }
+}
+
+pub fn detection<T: Scalar>(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = DataPoint<T>>,
+) -> Vec<DataPoint<T>> {
+    let mut stage = Detection::new(config);
 
-            None
-        })
+    input
+        .into_iter()
+        .filter_map(|this| stage.push(this))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_statistics_of_long_f32_input() {
+        let window = 200;
+        let value = |index: usize| 100.0 + (index as f64 * 0.1).sin();
+
+        let mut statistics = Statistics::<f32>::new(DetectionStatistics::Rolling { window });
+
+        // day of 25Hz score with big offset, where add-then-subtract loses precision
+        let len = 24 * 60 * 60 * 25;
+
+        for index in 0..len {
+            statistics.update(index, value(index) as f32);
+        }
+
+        let last = (len - window..len).map(value).collect::<Vec<_>>();
+        let mean = last.iter().sum::<f64>() / window as f64;
+        let std =
+            (last.iter().map(|this| (this - mean).powi(2)).sum::<f64>() / window as f64).sqrt();
+
+        let (actual_mean, actual_std) = statistics.get();
+
+        assert!(
+            (f64::from(actual_mean) - mean).abs() < 1e-3,
+            "{actual_mean} {mean}"
+        );
+        assert!(
+            (f64::from(actual_std) - std).abs() < 1e-2,
+            "{actual_std} {std}"
+        );
+    }
+}
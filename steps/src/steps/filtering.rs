@@ -1,49 +1,88 @@
-use std::sync::LazyLock;
+use alloc::{collections::VecDeque, vec::Vec};
 
-use crate::steps::DataPoint;
+use crate::steps::{DataPoint, Scalar, StepCounterConfig};
 
-const FILTER_LENGTH: usize = 13;
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
 
-static FILTER_COEF: LazyLock<Vec<f64>> = LazyLock::new(|| {
-    const FILTER_STD: f64 = 0.35;
+pub(crate) const FILTER_LENGTH: usize = 13;
+pub(crate) const FILTER_STD: f64 = 0.35;
 
-    (0..FILTER_LENGTH)
+/// Coefficients of gaussian filter with `length` points
+fn coefficients(length: usize, filter_std: f64) -> Vec<f64> {
+    (0..length)
         .map(|i| {
-            std::f64::consts::E.powf(
-                -0.5 * ((i as f64 - ((FILTER_LENGTH - 1) as f64) / 2.0)
-                    / (FILTER_STD * ((FILTER_LENGTH - 1) as f64) / 2.0))
+            core::f64::consts::E.powf(
+                -0.5 * ((i as f64 - ((length - 1) as f64) / 2.0)
+                    / (filter_std * ((length - 1) as f64) / 2.0))
                     .powi(2),
             )
         })
         .collect()
-});
+}
 
-static FILTER_SUM: LazyLock<f64> = LazyLock::new(|| FILTER_COEF.iter().sum());
+/// Gaussian low-pass filter, emits a point once window is full.
+#[derive(Debug, Clone)]
+pub(crate) struct Filtering<T = f64> {
+    filter_coef: Vec<T>,
+    filter_sum: T,
+    window: VecDeque<DataPoint<T>>,
+}
 
-pub fn filtering(input: impl IntoIterator<Item = DataPoint>) -> Vec<DataPoint> {
-    let input = input.into_iter().collect::<Vec<_>>();
+impl<T: Scalar> Filtering<T> {
+    pub fn new(config: &StepCounterConfig) -> Self {
+        let filter_coef = coefficients(config.filter_length, config.filter_std);
+        let filter_sum = T::from_f64(filter_coef.iter().sum());
 
-    if input.len() < FILTER_LENGTH {
-        return Vec::new();
+        Self {
+            window: VecDeque::with_capacity(filter_coef.len() + 1),
+            filter_coef: filter_coef.into_iter().map(T::from_f64).collect(),
+            filter_sum,
+        }
     }
 
-    input
-        .windows(FILTER_LENGTH)
-        .map(|this| {
-            let sum = this
-                .iter()
-                .enumerate()
-                .map(|(i, this)| this.magnitude * FILTER_COEF.get(i).copied().unwrap_or_default())
-                .sum::<f64>();
-
-            DataPoint {
-                magnitude: sum / *FILTER_SUM,
-                timestamp: this
-                    .get(FILTER_LENGTH / 2)
-                    .cloned()
-                    .expect("window is longer that search element")
-                    .timestamp,
-            }
+    pub fn push(&mut self, point: DataPoint<T>) -> Option<DataPoint<T>> {
+        let filter_length = self.filter_coef.len();
+
+        if filter_length == 0 {
+            return None;
+        }
+
+        self.window.push_back(point);
+
+        if self.window.len() > filter_length {
+            self.window.pop_front();
+        }
+
+        if self.window.len() < filter_length {
+            return None;
+        }
+
+        let sum = self
+            .window
+            .iter()
+            .zip(&self.filter_coef)
+            .fold(T::ZERO, |sum, (this, coef)| sum + this.magnitude * *coef);
+
+        Some(DataPoint {
+            magnitude: sum / self.filter_sum,
+            timestamp: self
+                .window
+                .get(filter_length / 2)
+                .expect("window is longer that search element")
+                .timestamp,
         })
+    }
+}
+
+pub fn filtering<T: Scalar>(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = DataPoint<T>>,
+) -> Vec<DataPoint<T>> {
+    let mut stage = Filtering::new(config);
+
+    input
+        .into_iter()
+        .filter_map(|this| stage.push(this))
         .collect()
 }
@@ -1,58 +1,183 @@
-use std::time::Duration;
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
 
-use crate::steps::DataPoint;
+use crate::steps::{DataPoint, Scalar, StepCounterConfig};
 
-const INTERPOLATION_TIME: Duration = Duration::from_millis(10);
+pub(crate) const INTERPOLATION_TIME: Duration = Duration::from_millis(10);
+/// Recommended [`StepCounterConfig::interpolation_max_gap`], default config doesn't split on gaps
+pub const INTERPOLATION_MAX_GAP: Duration = Duration::from_secs(1);
 
-pub fn interpolation(input: impl IntoIterator<Item = DataPoint>) -> Vec<DataPoint> {
-    let input = input.into_iter().collect::<Vec<_>>();
+/// Resamples signal to points placed every [`StepCounterConfig::interpolation_time`]
+/// in a single pass.
+///
+/// Signal is split into segments when gap between two points is bigger than
+/// [`StepCounterConfig::interpolation_max_gap`] or time goes backwards,
+/// nothing is interpolated inside of such gap and grid of the new segment
+/// starts from its first point.
+#[derive(Debug, Clone)]
+pub(crate) struct Interpolation<T = f64> {
+    interpolation_time: Duration,
+    max_gap: Option<Duration>,
+    previous: Option<DataPoint<T>>,
+    /// Timestamp of the next point to interpolate
+    next: Duration,
+}
 
-    if input.len() < 2 {
-        return Vec::new();
+impl<T: Scalar> Interpolation<T> {
+    pub fn new(config: &StepCounterConfig) -> Self {
+        Self {
+            interpolation_time: config.interpolation_time,
+            max_gap: config.interpolation_max_gap,
+            previous: None,
+            next: Duration::ZERO,
+        }
     }
 
-    let start_time = input.first().cloned().expect("not empty").timestamp;
+    /// Pushes interpolated points to `output`.
+    ///
+    /// Returns `true` when `point` starts new segment after a gap.
+    pub fn push(&mut self, point: DataPoint<T>, output: &mut Vec<DataPoint<T>>) -> bool {
+        if self.interpolation_time.is_zero() {
+            return false;
+        }
 
-    let input = input
-        .into_iter()
-        .map(|mut this| {
-            this.timestamp -= start_time;
-            this
-        })
-        .collect::<Vec<_>>();
+        let Some(previous) = self.previous.replace(point.clone()) else {
+            self.next = point.timestamp;
+            return false;
+        };
 
-    let mut output = Vec::new();
-    let mut window = input.clone();
-    let mut interpolation_count = 0;
+        let dt = match point.timestamp.checked_sub(previous.timestamp) {
+            Some(dt) if self.max_gap.is_none_or(|max_gap| dt <= max_gap) => dt,
+            _ => {
+                self.next = point.timestamp;
+                return true;
+            }
+        };
 
-    while window.len() >= 2 {
-        let time1 = window[0].timestamp;
-        let time2 = window[1].timestamp;
+        let dv = point.magnitude - previous.magnitude;
+        let dt = T::from_duration(dt);
 
-        let number_of_points =
-            (time2.as_millis() - time1.as_millis()) / INTERPOLATION_TIME.as_millis();
+        while self.next < point.timestamp {
+            let magnitude =
+                previous.magnitude + dv * (T::from_duration(self.next - previous.timestamp) / dt);
 
-        for _ in 0..number_of_points {
-            let interp_time = interpolation_count * INTERPOLATION_TIME;
+            output.push(DataPoint {
+                magnitude,
+                timestamp: self.next,
+            });
 
-            if time1 <= interp_time && interp_time < time2 {
-                let dt = window[1].timestamp - window[0].timestamp;
-                let dv = window[1].magnitude - window[0].magnitude;
+            self.next += self.interpolation_time;
+        }
 
-                // `as` should be save as we reduce duration to difference in start
-                let magnitude = (dv / dt.as_millis() as f64)
-                    * (interp_time - window[0].timestamp).as_millis() as f64
-                    + window[0].magnitude;
+        false
+    }
+}
 
-                output.push(DataPoint {
-                    magnitude,
-                    timestamp: interp_time,
-                });
-                interpolation_count += 1;
-            }
+/// Interpolates signal and returns its segments separated by gaps.
+pub fn interpolation<T: Scalar>(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = DataPoint<T>>,
+) -> Vec<Vec<DataPoint<T>>> {
+    let mut stage = Interpolation::new(config);
+    let mut segments = vec![Vec::new()];
+
+    input.into_iter().for_each(|this| {
+        let mut output = Vec::new();
+
+        if stage.push(this, &mut output) {
+            segments.push(Vec::new());
+        }
+
+        segments
+            .last_mut()
+            .expect("at least one segment")
+            .append(&mut output);
+    });
+
+    segments.retain(|this| !this.is_empty());
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(millis: u64, magnitude: f64) -> DataPoint {
+        DataPoint {
+            magnitude,
+            timestamp: Duration::from_millis(millis),
         }
+    }
+
+    #[test]
+    fn linear_between_points() {
+        let config = StepCounterConfig::default();
+
+        let segments = interpolation(&config, [point(1000, 1.0), point(1040, 2.0)]);
+
+        assert_eq!(segments.len(), 1);
+
+        let actual = segments[0]
+            .iter()
+            .map(|this| (this.timestamp.as_millis(), this.magnitude))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            vec![(1000, 1.0), (1010, 1.25), (1020, 1.5), (1030, 1.75)]
+        );
+    }
+
+    #[test]
+    fn split_on_gap() {
+        let config = StepCounterConfig::builder()
+            .interpolation_max_gap(Some(Duration::from_millis(100)))
+            .build();
+
+        let segments = interpolation(
+            &config,
+            [
+                point(0, 1.0),
+                point(40, 1.0),
+                point(5000, 1.0),
+                point(5040, 1.0),
+                point(5080, 1.0),
+            ],
+        );
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].len(), 4);
+        assert_eq!(segments[1].len(), 8);
+        assert_eq!(segments[1][0].timestamp, Duration::from_millis(5000));
+    }
+
+    #[test]
+    fn split_on_time_going_backwards() {
+        let config = StepCounterConfig::default();
+
+        let segments = interpolation(
+            &config,
+            [
+                point(100, 1.0),
+                point(140, 1.0),
+                point(50, 1.0),
+                point(90, 1.0),
+            ],
+        );
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[1][0].timestamp, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn no_gaps_without_limit() {
+        let config = StepCounterConfig::builder()
+            .interpolation_max_gap(None)
+            .build();
+
+        let segments = interpolation(&config, [point(0, 0.0), point(5000, 1.0)]);
 
-        window.remove(0);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].len(), 500);
     }
-    output
 }
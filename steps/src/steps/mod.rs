@@ -1,41 +1,181 @@
+mod cadence;
+mod config;
+mod counter;
 mod detection;
 mod filtering;
 mod intepolation;
+mod placement;
+mod preprocessing;
+mod sample_rate;
+mod scalar;
 mod scoring;
 mod time_threshold;
+mod trace;
+mod units;
 
-#[derive(Debug, Clone)]
-struct DataPoint {
-    pub magnitude: f64,
-    pub timestamp: std::time::Duration,
+use alloc::vec::Vec;
+
+pub use self::intepolation::INTERPOLATION_MAX_GAP;
+pub(crate) use self::{intepolation::interpolation, preprocessing::preprocessing};
+
+use crate::{StepsError, validate_accelerometer};
+
+pub use self::{
+    cadence::*,
+    config::*,
+    counter::*,
+    detection::DetectionStatistics,
+    placement::*,
+    preprocessing::{BandPass, GRAVITY_CUTOFF_HZ, Preprocessing},
+    sample_rate::*,
+    scalar::*,
+    trace::*,
+    units::*,
+};
+
+/// Point of signal processed by windowed peak detection stages.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataPoint<T = f64> {
+    /// Magnitude of acceleration or score, depends on stage
+    pub magnitude: T,
+    pub timestamp: core::time::Duration,
 }
 
+/// Accelerometer sample, axes are `f64` by default, see [`Scalar`] for other types.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct Accelerometer {
-    pub timestamp: std::time::Duration,
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+pub struct Accelerometer<T = f64> {
+    pub timestamp: core::time::Duration,
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Scalar> From<Accelerometer<T>> for DataPoint<T> {
+    fn from(Accelerometer { timestamp, x, y, z }: Accelerometer<T>) -> Self {
+        Self {
+            magnitude: (x * x + y * y + z * z).sqrt(),
+            timestamp,
+        }
+    }
+}
+
+/// Step detected by windowed peak detection.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StepEvent {
+    /// UNIX timestamp of the step, same time base as input [`Accelerometer::timestamp`]
+    pub timestamp: core::time::Duration,
+    /// Score of the peak, the higher it is the more distinct step was
+    pub score: f64,
 }
 
-impl From<Accelerometer> for DataPoint {
-    fn from(Accelerometer { timestamp, x, y, z }: Accelerometer) -> Self {
+impl<T: Scalar> From<DataPoint<T>> for StepEvent {
+    fn from(
+        DataPoint {
+            magnitude,
+            timestamp,
+        }: DataPoint<T>,
+    ) -> Self {
         Self {
-            magnitude: (x.powi(2) + y.powi(2) + z.powi(2)).sqrt(),
             timestamp,
+            score: magnitude.to_f64(),
         }
     }
 }
 
-pub fn steps_count(input: impl IntoIterator<Item = Accelerometer>) -> usize {
-    let after_processing = intepolation::interpolation(input.into_iter().map(DataPoint::from));
-    let after_filter = filtering::filtering(after_processing);
-    let after_scoring = scoring::scoring(after_filter);
-    let after_detection = detection::detection(after_scoring);
-    let after_time = time_threshold::time_threshold(after_detection);
+/// Count steps with windowed peak detection using [`StepCounterConfig::default`].
+pub fn steps_count<T: Scalar>(input: impl IntoIterator<Item = Accelerometer<T>>) -> usize {
+    steps_count_with(&StepCounterConfig::default(), input)
+}
+
+/// Count steps with windowed peak detection tuned by `config`.
+pub fn steps_count_with<T: Scalar>(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = Accelerometer<T>>,
+) -> usize {
+    peak_detection(config, input).len()
+}
+
+/// Detect steps with windowed peak detection using [`StepCounterConfig::default`].
+pub fn step_events<T: Scalar>(input: impl IntoIterator<Item = Accelerometer<T>>) -> Vec<StepEvent> {
+    step_events_with(&StepCounterConfig::default(), input)
+}
+
+/// Detect steps with windowed peak detection tuned by `config`.
+///
+/// Steps are sorted by timestamp in asc order.
+/// Signal is processed in scalar type of `input`, e.g. `f32` or [`Q16`].
+pub fn step_events_with<T: Scalar>(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = Accelerometer<T>>,
+) -> Vec<StepEvent> {
+    peak_detection(config, input)
+        .into_iter()
+        .map(StepEvent::from)
+        .collect()
+}
+
+/// Count steps with windowed peak detection using [`StepCounterConfig::default`],
+/// see [`try_step_events_with`] for checks.
+pub fn try_steps_count(
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> Result<usize, StepsError> {
+    try_steps_count_with(&StepCounterConfig::default(), input)
+}
+
+/// Count steps with windowed peak detection tuned by `config`,
+/// see [`try_step_events_with`] for checks.
+pub fn try_steps_count_with(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> Result<usize, StepsError> {
+    try_step_events_with(config, input).map(|this| this.len())
+}
+
+/// Detect steps with windowed peak detection using [`StepCounterConfig::default`],
+/// see [`try_step_events_with`] for checks.
+pub fn try_step_events(
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> Result<Vec<StepEvent>, StepsError> {
+    try_step_events_with(&StepCounterConfig::default(), input)
+}
+
+/// Detect steps with windowed peak detection tuned by `config`.
+///
+/// Fails instead of returning no steps if config is invalid, input isn't sorted by timestamp,
+/// has NaN or infinite axes or is shorter than [`StepCounterConfig::min_duration`].
+pub fn try_step_events_with(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> Result<Vec<StepEvent>, StepsError> {
+    let input = input.into_iter().collect::<Vec<_>>();
+
+    config.validate()?;
+    validate_accelerometer(
+        &input,
+        config.min_duration().unwrap_or(core::time::Duration::MAX),
+    )?;
 
-    after_time.len()
+    Ok(step_events_with(config, input))
+}
+
+fn peak_detection<T: Scalar>(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = Accelerometer<T>>,
+) -> Vec<DataPoint<T>> {
+    let after_processing = preprocessing::preprocessing(config, input);
+    let after_scoring = intepolation::interpolation(config, after_processing)
+        .into_iter()
+        .flat_map(|segment| {
+            let after_filter = filtering::filtering(config, segment);
+            scoring::scoring(config, after_filter)
+        })
+        .collect::<Vec<_>>();
+    let after_detection = detection::detection(config, after_scoring);
+
+    time_threshold::time_threshold(config, after_detection)
 }
 
 #[cfg(test)]
@@ -47,7 +187,7 @@ mod tests {
 
     use super::*;
 
-    #[derive(Debug, serde::Deserialize)]
+    #[derive(Debug, Clone, serde::Deserialize)]
     struct TestDataCsv {
         pub timestamp: PrimitiveDateTime,
         pub x: f64,
@@ -86,27 +226,266 @@ mod tests {
         expected: usize,
         actual: usize,
         precision: f64,
+        autocorrelation: usize,
+    }
+
+    fn read_dataset(file_name: &str) -> Vec<TestDataCsv> {
+        let mut rdr =
+            csv::Reader::from_reader(File::open(format!("assets/wrist_25hz/{file_name}")).unwrap());
+
+        rdr.deserialize::<TestDataCsv>()
+            .filter_map(|this| this.ok())
+            .collect()
+    }
+
+    fn annotated_steps(data: &[TestDataCsv]) -> Vec<core::time::Duration> {
+        crate::annotated_steps(
+            data.iter()
+                .map(|this| (Accelerometer::from(this.clone()).timestamp, this.annotation)),
+        )
     }
 
     #[test]
-    fn test_25() {
-        let report = RwLock::new(Vec::<ReportRecord>::with_capacity(39));
+    fn step_events_absolute_timestamps() {
+        let data = read_dataset("P37_wrist25.csv")
+            .into_iter()
+            .map(Accelerometer::from)
+            .collect::<Vec<_>>();
+
+        let start = data.first().unwrap().timestamp;
+        let end = data.last().unwrap().timestamp;
+
+        let events = step_events(data.clone());
+
+        assert_eq!(events.len(), steps_count(data));
+        assert!(!events.is_empty());
+        assert!(
+            events
+                .iter()
+                .all(|this| start <= this.timestamp && this.timestamp <= end)
+        );
+        assert!(
+            events
+                .windows(2)
+                .all(|this| this[0].timestamp < this[1].timestamp)
+        );
+    }
+
+    #[test]
+    fn step_counter_matches_batch() {
+        let data = read_dataset("P37_wrist25.csv")
+            .into_iter()
+            .map(Accelerometer::from)
+            .collect::<Vec<_>>();
+
+        let configs = [
+            StepCounterConfig::default(),
+            StepCounterConfig::builder()
+                .preprocessing(Preprocessing {
+                    gravity_removal: Some(GRAVITY_CUTOFF_HZ),
+                    band_pass: Some(BandPass::WALKING),
+                })
+                .detection_statistics(DetectionStatistics::Rolling { window: 1000 })
+                .build(),
+        ];
+
+        for config in configs {
+            let expected = step_events_with(&config, data.clone());
+
+            for chunk_size in [1, 7, 25, 1000] {
+                let mut counter = StepCounter::new(config.clone());
+
+                let actual = data
+                    .chunks(chunk_size)
+                    .flat_map(|chunk| counter.push(chunk.iter().cloned()))
+                    .collect::<Vec<_>>();
+
+                assert_eq!(expected, actual);
+                assert_eq!(expected.len(), counter.count());
+            }
+        }
+    }
+
+    #[test]
+    fn adaptive_statistics_closer_to_annotation() {
+        let data = read_dataset("P13_wrist25.csv");
+
+        let expected = data.iter().map(|this| this.annotation).sum::<usize>() as f64;
+
+        let data = data
+            .into_iter()
+            .map(Accelerometer::from)
+            .collect::<Vec<_>>();
+
+        let error = |detection_statistics| {
+            let config = StepCounterConfig::builder()
+                .detection_statistics(detection_statistics)
+                .build();
+
+            (steps_count_with(&config, data.clone()) as f64 - expected).abs() / expected
+        };
 
-        (1..40).into_par_iter().for_each(|i| {
-            let file_name = format!("P{i:02}_wrist25.csv");
+        let initial = error(DetectionStatistics::Initial);
+        let rolling = error(DetectionStatistics::Rolling { window: 1000 });
+        let exponential = error(DetectionStatistics::Exponential { alpha: 0.003 });
 
-            let mut rdr = csv::Reader::from_reader(
-                File::open(format!("assets/wrist_25hz/{file_name}")).unwrap(),
+        assert!(rolling < 0.05, "{rolling}");
+        assert!(exponential < 0.05, "{exponential}");
+        assert!(rolling < initial && exponential < initial);
+    }
+
+    #[test]
+    fn traced_matches_events() {
+        let data = read_dataset("P37_wrist25.csv")
+            .into_iter()
+            .map(Accelerometer::from)
+            .collect::<Vec<_>>();
+
+        let trace = steps_count_traced(data.clone());
+
+        let expected = step_events(data);
+        let actual = trace
+            .steps
+            .iter()
+            .cloned()
+            .map(StepEvent::from)
+            .collect::<Vec<_>>();
+
+        assert_eq!(expected, actual);
+        assert!(trace.interpolated.len() > trace.filtered.len());
+        assert!(trace.filtered.len() > trace.scored.len());
+        assert!(trace.scored.len() > trace.detected.len());
+        assert!(trace.detected.len() > trace.count());
+    }
+
+    #[test]
+    fn autocorrelation_on_wrist() {
+        for file_name in ["P13_wrist25.csv", "P37_wrist25.csv"] {
+            let data = read_dataset(file_name);
+
+            let expected = data.iter().map(|this| this.annotation).sum::<usize>() as f64;
+            let actual =
+                crate::steps_count_autocorrelation(data.into_iter().map(Accelerometer::from));
+
+            let error = (actual as f64 - expected).abs() / expected;
+
+            assert!(error < 0.1, "{file_name}: {actual} of {expected}");
+        }
+    }
+
+    #[test]
+    fn wrist_sample_rate() {
+        let config = StepCounterConfig::default();
+
+        for file_name in ["P13_wrist25.csv", "P37_wrist25.csv"] {
+            let data = read_dataset(file_name)
+                .into_iter()
+                .map(|this| Accelerometer::from(this).timestamp);
+
+            let actual = estimate_sample_rate(data, config.interpolation_max_gap).unwrap();
+
+            assert!(
+                (actual.rate_hz() - 25.0).abs() < 1.0,
+                "{file_name}: {actual:?}"
             );
+            assert!(config.check_sample_rate(Some(actual)).is_ok());
+        }
+    }
+
+    #[test]
+    fn wrist_placement_on_annotated_data() {
+        let f1 = |config: &StepCounterConfig| {
+            let recordings = ["P13_wrist25.csv", "P37_wrist25.csv"].map(|file_name| {
+                let data = read_dataset(file_name);
+
+                let expected = annotated_steps(&data);
+                let predicted = step_events_with(config, data.into_iter().map(Accelerometer::from))
+                    .into_iter()
+                    .map(|this| this.timestamp)
+                    .collect::<Vec<_>>();
 
-            let data = rdr
-                .deserialize::<TestDataCsv>()
-                .filter_map(|this| this.ok())
+                (file_name, predicted, expected)
+            });
+
+            crate::evaluate(recordings, crate::TOLERANCE)
+                .pop()
+                .unwrap()
+                .f1
+        };
+
+        let wrist = f1(&Placement::Wrist.config());
+        let default = f1(&StepCounterConfig::default());
+
+        assert!(wrist > 0.75, "{wrist}");
+        assert!(wrist > default, "{wrist} <= {default}");
+    }
+
+    #[test]
+    fn scalar_types_close_to_f64() {
+        let configs = [
+            StepCounterConfig::default(),
+            Placement::Wrist.config(),
+            Placement::Hip.config(),
+        ];
+
+        for file_name in ["P13_wrist25.csv", "P37_wrist25.csv"] {
+            let data = read_dataset(file_name)
+                .into_iter()
+                .map(Accelerometer::from)
                 .collect::<Vec<_>>();
 
+            for config in &configs {
+                let expected = steps_count_with(config, data.clone()) as f64;
+
+                let f32 =
+                    steps_count_with(config, data.iter().cloned().map(Accelerometer::cast::<f32>));
+                let q16 =
+                    steps_count_with(config, data.iter().cloned().map(Accelerometer::cast::<Q16>));
+
+                let mut counter = StepCounter::<Q16>::with_scalar(config.clone());
+                let streamed = data
+                    .chunks(25)
+                    .map(|chunk| {
+                        counter
+                            .push(chunk.iter().cloned().map(Accelerometer::cast))
+                            .len()
+                    })
+                    .sum::<usize>();
+
+                assert_eq!(streamed, q16);
+                assert!(
+                    (f32 as f64 - expected).abs() / expected < 0.01,
+                    "{file_name}: f32 {f32} of {expected}"
+                );
+                assert!(
+                    (q16 as f64 - expected).abs() / expected < 0.01,
+                    "{file_name}: q16 {q16} of {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_25() {
+        let report = RwLock::new(Vec::<ReportRecord>::with_capacity(39));
+
+        let file_names = std::fs::read_dir("assets/wrist_25hz")
+            .unwrap()
+            .map(|this| this.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        file_names.into_par_iter().for_each(|file_name| {
+            let data = read_dataset(&file_name);
+
             let expected = data.iter().map(|this| this.annotation).sum::<usize>();
 
-            let actual = steps_count(data.into_iter().map(Accelerometer::from));
+            let data = data
+                .into_iter()
+                .map(Accelerometer::from)
+                .collect::<Vec<_>>();
+
+            let actual = steps_count(data.clone());
+            let autocorrelation = crate::steps_count_autocorrelation(data);
 
             let precision = match expected < actual {
                 true => expected as f64 / actual as f64,
@@ -114,7 +493,7 @@ mod tests {
             };
 
             let precision = match precision.is_sign_negative() {
-                true => precision * -1.0,
+                true => -precision,
                 false => precision,
             };
 
@@ -123,6 +502,7 @@ mod tests {
                 expected,
                 actual,
                 precision,
+                autocorrelation,
             });
         });
 
@@ -136,4 +516,57 @@ mod tests {
 
         wr.flush().unwrap();
     }
+
+    #[test]
+    fn evaluation_25() {
+        let mut file_names = std::fs::read_dir("assets/wrist_25hz")
+            .unwrap()
+            .map(|this| this.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        file_names.sort();
+
+        let recordings = file_names
+            .into_par_iter()
+            .map(|file_name| {
+                let data = read_dataset(&file_name);
+
+                let expected = annotated_steps(&data);
+                let predicted = step_events(data.into_iter().map(Accelerometer::from))
+                    .into_iter()
+                    .map(|this| this.timestamp)
+                    .collect::<Vec<_>>();
+
+                (file_name, predicted, expected)
+            })
+            .collect::<Vec<_>>();
+
+        let evaluations = crate::evaluate(recordings, crate::TOLERANCE);
+
+        for this in &evaluations {
+            assert_eq!(
+                this.true_positives + this.false_negatives,
+                this.expected,
+                "{}",
+                this.name
+            );
+            assert_eq!(
+                this.true_positives + this.false_positives,
+                this.predicted,
+                "{}",
+                this.name
+            );
+        }
+
+        let total = evaluations.last().unwrap();
+
+        assert_eq!(total.name, "total");
+        assert!(
+            total.f1 > 0.5,
+            "precision {:.3}, recall {:.3}, f1 {:.3}",
+            total.precision,
+            total.recall,
+            total.f1
+        );
+    }
 }
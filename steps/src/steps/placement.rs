@@ -0,0 +1,79 @@
+use core::time::Duration;
+
+use crate::steps::{BandPass, DetectionStatistics, Preprocessing, StepCounterConfig};
+
+/// Where device is worn, every placement has its own [`StepCounterConfig`].
+///
+/// Only [`Placement::Wrist`] is validated on annotated data, other profiles are provisional.
+///
+/// ```
+/// use steps::{Placement, StepCounterConfig};
+///
+/// let config = StepCounterConfig::from(Placement::Wrist);
+///
+/// assert_eq!(config, Placement::Wrist.config());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Placement {
+    /// Watch or band, validated on `assets/wrist_25hz`
+    #[default]
+    Wrist,
+    /// Clip on belt, signal is dominated by vertical oscillation of body.
+    ///
+    /// _Provisional_: parameters are picked from typical gait, not validated on annotated data
+    Hip,
+    /// Phone in trouser pocket, orientation of device changes during walk.
+    ///
+    /// _Provisional_: parameters are picked from typical gait, not validated on annotated data
+    Pocket,
+}
+
+impl Placement {
+    pub const ALL: [Self; 3] = [Self::Wrist, Self::Hip, Self::Pocket];
+
+    /// Full set of pipeline parameters for placement.
+    ///
+    /// Profiles except [`Placement::Wrist`] have no annotated data yet and use
+    /// typical gait frequencies of body part, tune them with `steps_tuning --config`
+    /// before relying on their counts.
+    pub fn config(self) -> StepCounterConfig {
+        match self {
+            Self::Wrist => StepCounterConfig::builder()
+                .detection_statistics(DetectionStatistics::Exponential { alpha: 0.003 })
+                .build(),
+            Self::Hip => StepCounterConfig::builder()
+                .preprocessing(Preprocessing {
+                    gravity_removal: None,
+                    band_pass: Some(BandPass::WALKING),
+                })
+                .filter_std(0.5)
+                .scoring_size(31)
+                .detection_threshold(1.0)
+                .detection_statistics(DetectionStatistics::Exponential { alpha: 0.003 })
+                .time_threshold(Duration::from_millis(250))
+                .build(),
+            Self::Pocket => StepCounterConfig::builder()
+                .preprocessing(Preprocessing {
+                    gravity_removal: None,
+                    band_pass: Some(BandPass::WALKING),
+                })
+                .filter_std(0.5)
+                .scoring_size(31)
+                .detection_threshold(1.1)
+                .detection_statistics(DetectionStatistics::Rolling { window: 1000 })
+                .time_threshold(Duration::from_millis(250))
+                .build(),
+        }
+    }
+}
+
+impl From<Placement> for StepCounterConfig {
+    fn from(placement: Placement) -> Self {
+        placement.config()
+    }
+}
@@ -0,0 +1,253 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::steps::{Accelerometer, DataPoint, Scalar, StepCounterConfig};
+
+/// Default cutoff frequency of gravity estimation.
+pub const GRAVITY_CUTOFF_HZ: f64 = 0.3;
+
+/// Frequency band of a filter in Hz.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BandPass {
+    pub low_hz: f64,
+    pub high_hz: f64,
+}
+
+impl BandPass {
+    /// Typical frequencies of walking and running
+    pub const WALKING: Self = Self {
+        low_hz: 0.5,
+        high_hz: 3.0,
+    };
+}
+
+/// Processing of raw accelerometer data before interpolation.
+///
+/// [`Default`] doesn't change signal and uses raw magnitude of acceleration vector.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Preprocessing {
+    /// Cutoff frequency in Hz of low-pass filter which estimates gravity on every axis.
+    /// Gravity is subtracted from axes before magnitude is calculated,
+    /// so slow wrist rotation doesn't change the signal. See [`GRAVITY_CUTOFF_HZ`]
+    pub gravity_removal: Option<f64>,
+    /// Band-pass filter of magnitude e.g. [`BandPass::WALKING`]
+    pub band_pass: Option<BandPass>,
+}
+
+/// First order low-pass filter which supports irregular time between samples.
+#[derive(Debug, Clone)]
+struct LowPass<T> {
+    /// Time constant of the filter in seconds
+    rc: T,
+    value: Option<T>,
+}
+
+impl<T: Scalar> LowPass<T> {
+    fn new(cutoff_hz: f64) -> Self {
+        Self {
+            rc: T::from_f64(1.0 / (2.0 * core::f64::consts::PI * cutoff_hz)),
+            value: None,
+        }
+    }
+
+    fn push(&mut self, value: T, dt: Duration) -> T {
+        let output = match self.value {
+            Some(previous) => {
+                let dt = T::from_duration(dt);
+                previous + (value - previous) * (dt / (self.rc + dt))
+            }
+            None => value,
+        };
+
+        self.value = Some(output);
+        output
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Filters<T> {
+    /// Gravity on x, y and z axes
+    gravity: Option<[LowPass<T>; 3]>,
+    /// Removes slow changes of magnitude
+    high_pass: Option<LowPass<T>>,
+    /// Removes fast changes of magnitude
+    low_pass: Option<LowPass<T>>,
+}
+
+impl<T: Scalar> Filters<T> {
+    fn new(
+        Preprocessing {
+            gravity_removal,
+            band_pass,
+        }: Preprocessing,
+    ) -> Self {
+        Self {
+            gravity: gravity_removal.map(|cutoff_hz| {
+                [
+                    LowPass::new(cutoff_hz),
+                    LowPass::new(cutoff_hz),
+                    LowPass::new(cutoff_hz),
+                ]
+            }),
+            high_pass: band_pass.map(|BandPass { low_hz, .. }| LowPass::new(low_hz)),
+            low_pass: band_pass.map(|BandPass { high_hz, .. }| LowPass::new(high_hz)),
+        }
+    }
+
+    fn push(
+        &mut self,
+        Accelerometer { timestamp, x, y, z }: Accelerometer<T>,
+        dt: Duration,
+    ) -> DataPoint<T> {
+        let [x, y, z] = match &mut self.gravity {
+            Some([gravity_x, gravity_y, gravity_z]) => [
+                x - gravity_x.push(x, dt),
+                y - gravity_y.push(y, dt),
+                z - gravity_z.push(z, dt),
+            ],
+            None => [x, y, z],
+        };
+
+        let mut magnitude = (x * x + y * y + z * z).sqrt();
+
+        if let Some(high_pass) = &mut self.high_pass {
+            magnitude = magnitude - high_pass.push(magnitude, dt);
+        }
+
+        if let Some(low_pass) = &mut self.low_pass {
+            magnitude = low_pass.push(magnitude, dt);
+        }
+
+        DataPoint {
+            magnitude,
+            timestamp,
+        }
+    }
+}
+
+/// Applies [`Preprocessing`] sample by sample.
+///
+/// State of filters is reset on the same gaps which split interpolation.
+#[derive(Debug, Clone)]
+pub(crate) struct Preprocessor<T = f64> {
+    preprocessing: Preprocessing,
+    max_gap: Option<Duration>,
+    previous: Option<Duration>,
+    filters: Filters<T>,
+}
+
+impl<T: Scalar> Preprocessor<T> {
+    pub fn new(config: &StepCounterConfig) -> Self {
+        Self {
+            preprocessing: config.preprocessing,
+            max_gap: config.interpolation_max_gap,
+            previous: None,
+            filters: Filters::new(config.preprocessing),
+        }
+    }
+
+    pub fn push(&mut self, sample: Accelerometer<T>) -> DataPoint<T> {
+        let dt = self
+            .previous
+            .replace(sample.timestamp)
+            .and_then(|previous| sample.timestamp.checked_sub(previous))
+            .filter(|dt| self.max_gap.is_none_or(|max_gap| *dt <= max_gap));
+
+        match dt {
+            Some(dt) => self.filters.push(sample, dt),
+            None => {
+                self.filters = Filters::new(self.preprocessing);
+                self.filters.push(sample, Duration::ZERO)
+            }
+        }
+    }
+}
+
+pub fn preprocessing<T: Scalar>(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = Accelerometer<T>>,
+) -> Vec<DataPoint<T>> {
+    let mut stage = Preprocessor::new(config);
+
+    input.into_iter().map(|this| stage.push(this)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 25Hz samples for `seconds` seconds
+    fn samples(seconds: u32, f: impl Fn(f64) -> [f64; 3]) -> Vec<Accelerometer> {
+        (0..seconds * 25)
+            .map(|i| {
+                let timestamp = Duration::from_millis(u64::from(i) * 40);
+                let [x, y, z] = f(timestamp.as_secs_f64());
+
+                Accelerometer { timestamp, x, y, z }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn default_is_raw_magnitude() {
+        let input = samples(2, |t| [0.1 * t, 0.6, 0.8]);
+
+        let actual = preprocessing(&StepCounterConfig::default(), input.clone());
+        let expected = input.into_iter().map(DataPoint::from).collect::<Vec<_>>();
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn gravity_removal_of_tilted_device() {
+        let config = StepCounterConfig::builder()
+            .preprocessing(Preprocessing {
+                gravity_removal: Some(GRAVITY_CUTOFF_HZ),
+                band_pass: None,
+            })
+            .build();
+
+        let output = preprocessing(&config, samples(10, |_| [0.0, 0.6, 0.8]));
+
+        assert!(output.iter().all(|this| this.magnitude.abs() < 1e-9));
+    }
+
+    #[test]
+    fn band_pass_removes_drift() {
+        let config = StepCounterConfig::builder()
+            .preprocessing(Preprocessing {
+                gravity_removal: None,
+                band_pass: Some(BandPass::WALKING),
+            })
+            .build();
+
+        // 2Hz walking on top of slow drift
+        let output = preprocessing(
+            &config,
+            samples(60, |t| {
+                [
+                    0.0,
+                    0.0,
+                    1.0 + 0.02 * t + 0.3 * (2.0 * core::f64::consts::PI * 2.0 * t).sin(),
+                ]
+            }),
+        );
+
+        // skip filter warm up
+        let settled = &output[250..];
+        let mean = settled.iter().map(|this| this.magnitude).sum::<f64>() / settled.len() as f64;
+        let max = settled
+            .iter()
+            .map(|this| this.magnitude)
+            .fold(f64::MIN, f64::max);
+
+        assert!(mean.abs() < 0.05, "{mean}");
+        assert!(max > 0.1, "{max}");
+    }
+}
@@ -0,0 +1,267 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::steps::StepCounterConfig;
+
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+
+/// Minimal sample rate in Hz at which steps could be detected
+pub const MIN_SAMPLE_RATE_HZ: f64 = 10.0;
+/// Jitter relative to sampling period above which timestamps are reported as irregular
+pub const MAX_RELATIVE_JITTER: f64 = 0.25;
+
+/// Real sample rate of recording estimated from timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SampleRate {
+    /// Median time between two samples
+    pub period: Duration,
+    /// Standard deviation of time between two samples
+    pub jitter: Duration,
+    /// Number of gaps which were excluded from estimation
+    pub gaps: usize,
+}
+
+impl SampleRate {
+    pub fn rate_hz(&self) -> f64 {
+        1.0 / self.period.as_secs_f64()
+    }
+}
+
+/// Estimate sample rate and jitter from timestamps.
+///
+/// Intervals longer than `max_gap` are counted as gaps and excluded.
+/// Returns `None` if there are no intervals to estimate from.
+///
+/// # Params
+/// - `timestamps` - timestamps of samples sorted in asc order
+/// - `max_gap` - e.g. [`StepCounterConfig::interpolation_max_gap`]
+pub fn estimate_sample_rate(
+    timestamps: impl IntoIterator<Item = Duration>,
+    max_gap: Option<Duration>,
+) -> Option<SampleRate> {
+    let timestamps = timestamps.into_iter().collect::<Vec<_>>();
+
+    let (mut intervals, gaps) = timestamps
+        .windows(2)
+        .filter_map(|this| this[1].checked_sub(this[0]))
+        .filter(|this| !this.is_zero())
+        .partition::<Vec<_>, _>(|this| max_gap.is_none_or(|max_gap| *this <= max_gap));
+
+    if intervals.is_empty() {
+        return None;
+    }
+
+    intervals.sort();
+
+    let period = intervals[intervals.len() / 2];
+
+    let mean = intervals.iter().map(Duration::as_secs_f64).sum::<f64>() / intervals.len() as f64;
+    let jitter = (intervals
+        .iter()
+        .map(|this| (this.as_secs_f64() - mean).powi(2))
+        .sum::<f64>()
+        / intervals.len() as f64)
+        .sqrt();
+
+    Some(SampleRate {
+        period,
+        jitter: Duration::from_secs_f64(jitter),
+        gaps: gaps.len(),
+    })
+}
+
+/// Sample rate with which config can't detect steps.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum SampleRateError {
+    /// Less than two samples with increasing timestamps
+    Unknown,
+    /// Rate is below [`MIN_SAMPLE_RATE_HZ`] or below Nyquist rate of band-pass filter
+    TooLow { rate_hz: f64, min_hz: f64 },
+}
+
+impl core::fmt::Display for SampleRateError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Unknown => write!(
+                f,
+                "Sample rate can't be estimated from less than two samples"
+            ),
+            Self::TooLow { rate_hz, min_hz } => write!(
+                f,
+                "Sample rate {rate_hz:.1}Hz is below minimal supported {min_hz:.1}Hz"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for SampleRateError {}
+
+/// Sample rate with which config works, but results could be worse.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum SampleRateWarning {
+    /// Rate is above rate of interpolation, so part of samples is skipped
+    AboveInterpolation { rate_hz: f64, interpolation_hz: f64 },
+    /// Jitter is above [`MAX_RELATIVE_JITTER`] of sampling period
+    Irregular { jitter: Duration, period: Duration },
+    /// Interpolation is split on gaps, see [`StepCounterConfig::interpolation_max_gap`]
+    Gaps { count: usize },
+}
+
+impl core::fmt::Display for SampleRateWarning {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::AboveInterpolation {
+                rate_hz,
+                interpolation_hz,
+            } => write!(
+                f,
+                "Sample rate {rate_hz:.1}Hz is above interpolation rate {interpolation_hz:.1}Hz"
+            ),
+            Self::Irregular { jitter, period } => write!(
+                f,
+                "Jitter {jitter:?} is too big for sampling period {period:?}"
+            ),
+            Self::Gaps { count } => write!(f, "Recording has {count} gaps"),
+        }
+    }
+}
+
+impl StepCounterConfig {
+    /// Check if config supports sample rate of recording.
+    ///
+    /// Returns warnings if steps could be detected, but results could be worse.
+    ///
+    /// # Params
+    /// - `sample_rate` - e.g. from [`estimate_sample_rate`], `None` if it can't be estimated
+    pub fn check_sample_rate(
+        &self,
+        sample_rate: Option<SampleRate>,
+    ) -> Result<Vec<SampleRateWarning>, SampleRateError> {
+        let sample_rate = sample_rate.ok_or(SampleRateError::Unknown)?;
+
+        let rate_hz = sample_rate.rate_hz();
+        let min_hz = match self.preprocessing.band_pass {
+            Some(band_pass) => MIN_SAMPLE_RATE_HZ.max(2.0 * band_pass.high_hz),
+            None => MIN_SAMPLE_RATE_HZ,
+        };
+
+        if rate_hz < min_hz {
+            return Err(SampleRateError::TooLow { rate_hz, min_hz });
+        }
+
+        let mut warnings = Vec::new();
+
+        let interpolation_hz = 1.0 / self.interpolation_time.as_secs_f64();
+
+        if rate_hz > interpolation_hz {
+            warnings.push(SampleRateWarning::AboveInterpolation {
+                rate_hz,
+                interpolation_hz,
+            });
+        }
+
+        if sample_rate.jitter.as_secs_f64() > sample_rate.period.as_secs_f64() * MAX_RELATIVE_JITTER
+        {
+            warnings.push(SampleRateWarning::Irregular {
+                jitter: sample_rate.jitter,
+                period: sample_rate.period,
+            });
+        }
+
+        if sample_rate.gaps > 0 {
+            warnings.push(SampleRateWarning::Gaps {
+                count: sample_rate.gaps,
+            });
+        }
+
+        Ok(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::INTERPOLATION_MAX_GAP;
+
+    use super::*;
+
+    fn timestamps(period_ms: u64, count: u64) -> Vec<Duration> {
+        (0..count)
+            .map(|i| Duration::from_millis(i * period_ms))
+            .collect()
+    }
+
+    #[test]
+    fn regular_25hz() {
+        let config = StepCounterConfig::default();
+
+        let actual = estimate_sample_rate(timestamps(40, 100), config.interpolation_max_gap);
+
+        assert_eq!(
+            actual,
+            Some(SampleRate {
+                period: Duration::from_millis(40),
+                jitter: Duration::ZERO,
+                gaps: 0,
+            })
+        );
+        assert_eq!(config.check_sample_rate(actual), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn gaps_and_jitter() {
+        let config = StepCounterConfig::builder()
+            .interpolation_max_gap(Some(INTERPOLATION_MAX_GAP))
+            .build();
+
+        let mut data = timestamps(40, 100)
+            .into_iter()
+            .enumerate()
+            .map(|(i, this)| match i % 2 {
+                0 => this,
+                _ => this + Duration::from_millis(15),
+            })
+            .collect::<Vec<_>>();
+        data.extend(
+            timestamps(40, 100)
+                .iter()
+                .map(|this| *this + Duration::from_secs(60)),
+        );
+
+        let actual = estimate_sample_rate(data, config.interpolation_max_gap).unwrap();
+
+        assert_eq!(actual.gaps, 1);
+
+        let warnings = config.check_sample_rate(Some(actual)).unwrap();
+
+        assert!(matches!(
+            warnings.as_slice(),
+            [
+                SampleRateWarning::Irregular { .. },
+                SampleRateWarning::Gaps { count: 1 }
+            ]
+        ));
+    }
+
+    #[test]
+    fn too_low_and_too_high() {
+        let config = StepCounterConfig::default();
+
+        let low = estimate_sample_rate(timestamps(200, 100), None);
+        let high = estimate_sample_rate(timestamps(5, 100), None);
+
+        assert!(matches!(
+            config.check_sample_rate(low),
+            Err(SampleRateError::TooLow { .. })
+        ));
+        assert!(matches!(
+            config.check_sample_rate(high).unwrap().as_slice(),
+            [SampleRateWarning::AboveInterpolation { .. }]
+        ));
+        assert_eq!(
+            config.check_sample_rate(estimate_sample_rate([Duration::ZERO], None)),
+            Err(SampleRateError::Unknown)
+        );
+    }
+}
@@ -0,0 +1,402 @@
+use core::{
+    fmt::Debug,
+    ops::{Add, Div, Mul, Sub},
+    time::Duration,
+};
+
+use crate::steps::Accelerometer;
+
+/// Number type of signal in windowed peak detection.
+///
+/// Pipeline works in `f64` by default, `f32` and [`Q16`] are cheaper
+/// on microcontrollers with single precision FPU or without FPU at all.
+/// Time is always kept as [`core::time::Duration`].
+pub trait Scalar:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+{
+    /// Type of detection statistics, wider than `Self` for fixed-point numbers
+    /// so small changes of variance aren't rounded to zero
+    type Accumulator: Scalar + From<Self>;
+
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Convert from `f64`, out of range values are saturated
+    fn from_f64(value: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+
+    /// Seconds of `value` converted without `f64`, so hot loops stay in `Self`
+    fn from_duration(value: Duration) -> Self;
+
+    /// Convert count without `f64`, out of range values are saturated
+    fn from_usize(value: usize) -> Self;
+
+    /// Square root, `0` for negative values
+    fn sqrt(self) -> Self;
+}
+
+impl Scalar for f64 {
+    type Accumulator = Self;
+
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn from_duration(value: Duration) -> Self {
+        value.as_secs_f64()
+    }
+
+    fn from_usize(value: usize) -> Self {
+        value as f64
+    }
+
+    fn sqrt(self) -> Self {
+        if self < 0.0 {
+            return 0.0;
+        }
+
+        #[cfg(any(feature = "std", test))]
+        return f64::sqrt(self);
+        #[cfg(not(any(feature = "std", test)))]
+        return libm::sqrt(self);
+    }
+}
+
+impl Scalar for f32 {
+    type Accumulator = Self;
+
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+
+    fn from_duration(value: Duration) -> Self {
+        value.as_secs_f32()
+    }
+
+    fn from_usize(value: usize) -> Self {
+        value as f32
+    }
+
+    fn sqrt(self) -> Self {
+        if self < 0.0 {
+            return 0.0;
+        }
+
+        #[cfg(any(feature = "std", test))]
+        return f32::sqrt(self);
+        #[cfg(not(any(feature = "std", test)))]
+        return libm::sqrtf(self);
+    }
+}
+
+/// Nanoseconds in second
+const NANOS: u64 = 1_000_000_000;
+
+/// Signed fixed-point number in Q15.16 format: `i32` with 16 fractional bits.
+///
+/// Range is `[-32768, 32768)` with resolution `1 / 65536`.
+/// Q15 and Q31 keep only fraction in `[-1, 1)`, which doesn't fit magnitude of
+/// acceleration in g and sums of statistics, so integer part is kept as well.
+/// Arithmetic saturates instead of overflow and rounds to nearest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Q16(pub i32);
+
+impl Q16 {
+    pub const FRACTIONAL_BITS: u32 = 16;
+
+    fn saturate(value: i64) -> Self {
+        Self(value.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32)
+    }
+}
+
+impl Add for Q16 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Q16 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Q16 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let half = 1 << (Self::FRACTIONAL_BITS - 1);
+
+        Self::saturate((i64::from(self.0) * i64::from(rhs.0) + half) >> Self::FRACTIONAL_BITS)
+    }
+}
+
+impl Div for Q16 {
+    type Output = Self;
+
+    /// Division by zero saturates to the closest bound
+    fn div(self, rhs: Self) -> Self::Output {
+        match rhs.0 {
+            0 => match self.0 {
+                0 => Self(0),
+                value if value > 0 => Self(i32::MAX),
+                _ => Self(i32::MIN),
+            },
+            rhs => Self::saturate((i64::from(self.0) << Self::FRACTIONAL_BITS) / i64::from(rhs)),
+        }
+    }
+}
+
+impl Scalar for Q16 {
+    type Accumulator = Q32;
+
+    const ZERO: Self = Self(0);
+    const ONE: Self = Self(1 << Self::FRACTIONAL_BITS);
+
+    fn from_f64(value: f64) -> Self {
+        // `as` saturates and maps NaN to 0
+        Self((value * f64::from(Self::ONE.0)) as i32)
+    }
+
+    fn to_f64(self) -> f64 {
+        f64::from(self.0) / f64::from(Self::ONE.0)
+    }
+
+    fn from_duration(value: Duration) -> Self {
+        let fraction =
+            ((u64::from(value.subsec_nanos()) << Self::FRACTIONAL_BITS) + NANOS / 2) / NANOS;
+
+        Self::saturate(
+            i64::try_from(value.as_secs())
+                .unwrap_or(i64::MAX)
+                .saturating_mul(i64::from(Self::ONE.0))
+                .saturating_add(fraction as i64),
+        )
+    }
+
+    fn from_usize(value: usize) -> Self {
+        Self::saturate(
+            i64::try_from(value)
+                .unwrap_or(i64::MAX)
+                .saturating_mul(i64::from(Self::ONE.0)),
+        )
+    }
+
+    fn sqrt(self) -> Self {
+        match u64::try_from(self.0) {
+            Ok(value) => {
+                let value = value << Self::FRACTIONAL_BITS;
+                let root = value.isqrt();
+
+                // round to nearest, (root + 0.5)² = root² + root + 0.25
+                match value - root * root > root {
+                    true => Self(root as i32 + 1),
+                    false => Self(root as i32),
+                }
+            }
+            Err(_) => Self::ZERO,
+        }
+    }
+}
+
+/// Signed fixed-point number in Q31.32 format: `i64` with 32 fractional bits.
+///
+/// Accumulator of [`Q16`], range is `[-2³¹, 2³¹)` with resolution `1 / 2³²`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Q32(pub i64);
+
+impl Q32 {
+    pub const FRACTIONAL_BITS: u32 = 32;
+
+    fn saturate(value: i128) -> Self {
+        Self(value.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64)
+    }
+}
+
+impl From<Q16> for Q32 {
+    fn from(value: Q16) -> Self {
+        Self(i64::from(value.0) << (Self::FRACTIONAL_BITS - Q16::FRACTIONAL_BITS))
+    }
+}
+
+impl Add for Q32 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Q32 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Q32 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let half = 1 << (Self::FRACTIONAL_BITS - 1);
+
+        Self::saturate((i128::from(self.0) * i128::from(rhs.0) + half) >> Self::FRACTIONAL_BITS)
+    }
+}
+
+impl Div for Q32 {
+    type Output = Self;
+
+    /// Division by zero saturates to the closest bound
+    fn div(self, rhs: Self) -> Self::Output {
+        match rhs.0 {
+            0 => match self.0 {
+                0 => Self(0),
+                value if value > 0 => Self(i64::MAX),
+                _ => Self(i64::MIN),
+            },
+            rhs => Self::saturate((i128::from(self.0) << Self::FRACTIONAL_BITS) / i128::from(rhs)),
+        }
+    }
+}
+
+impl Scalar for Q32 {
+    type Accumulator = Self;
+
+    const ZERO: Self = Self(0);
+    const ONE: Self = Self(1 << Self::FRACTIONAL_BITS);
+
+    fn from_f64(value: f64) -> Self {
+        // `as` saturates and maps NaN to 0
+        Self((value * Self::ONE.0 as f64) as i64)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::ONE.0 as f64
+    }
+
+    fn from_duration(value: Duration) -> Self {
+        let fraction =
+            ((u64::from(value.subsec_nanos()) << Self::FRACTIONAL_BITS) + NANOS / 2) / NANOS;
+
+        Self::saturate(i128::from(value.as_secs()) * i128::from(Self::ONE.0) + i128::from(fraction))
+    }
+
+    fn from_usize(value: usize) -> Self {
+        Self::saturate(value as i128 * i128::from(Self::ONE.0))
+    }
+
+    fn sqrt(self) -> Self {
+        match u128::try_from(self.0) {
+            Ok(value) => {
+                let value = value << Self::FRACTIONAL_BITS;
+                let root = value.isqrt();
+
+                // round to nearest, (root + 0.5)² = root² + root + 0.25
+                match value - root * root > root {
+                    true => Self(root as i64 + 1),
+                    false => Self(root as i64),
+                }
+            }
+            Err(_) => Self::ZERO,
+        }
+    }
+}
+
+impl<T: Scalar> Accelerometer<T> {
+    /// Convert axes to other scalar type, e.g. `f64` samples to [`Q16`]
+    pub fn cast<U: Scalar>(self) -> Accelerometer<U> {
+        Accelerometer {
+            timestamp: self.timestamp,
+            x: U::from_f64(self.x.to_f64()),
+            y: U::from_f64(self.y.to_f64()),
+            z: U::from_f64(self.z.to_f64()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_point_arithmetic() {
+        let a = Q16::from_f64(1.5);
+        let b = Q16::from_f64(-0.25);
+
+        assert_eq!((a + b).to_f64(), 1.25);
+        assert_eq!((a - b).to_f64(), 1.75);
+        assert_eq!((a * b).to_f64(), -0.375);
+        assert_eq!((a / b).to_f64(), -6.0);
+        assert_eq!(Q16::from_f64(2.25).sqrt().to_f64(), 1.5);
+        assert_eq!(b.sqrt(), Q16::ZERO);
+        assert_eq!(
+            Q16::from_duration(Duration::from_millis(2250)).to_f64(),
+            2.25
+        );
+        assert_eq!(Q16::from_usize(34).to_f64(), 34.0);
+        assert!((Q32::from_duration(Duration::from_millis(40)).to_f64() - 0.04).abs() < 1e-9);
+    }
+
+    #[test]
+    fn accumulator_keeps_precision() {
+        let small = Q16::from_f64(0.002);
+
+        assert_eq!(small * small, Q16::ZERO);
+        assert_eq!(
+            (Q32::from(small) * Q32::from(small)).to_f64(),
+            small.to_f64().powi(2)
+        );
+        assert_eq!(Q32::from(Q16::from_f64(-2.5)).to_f64(), -2.5);
+        assert_eq!(Q32::from_f64(2.25).sqrt().to_f64(), 1.5);
+    }
+
+    #[test]
+    fn fixed_point_saturates() {
+        let max = Q16::from_f64(30000.0);
+
+        assert_eq!(max * max, Q16(i32::MAX));
+        assert_eq!(Q16::from_f64(-30000.0) - max, Q16(i32::MIN));
+        assert_eq!(Q16::ONE / Q16::ZERO, Q16(i32::MAX));
+        assert_eq!(Q16::from_f64(f64::NAN), Q16::ZERO);
+        assert_eq!(Q16::from_duration(Duration::MAX), Q16(i32::MAX));
+        assert_eq!(Q16::from_usize(usize::MAX), Q16(i32::MAX));
+    }
+}
@@ -1,35 +1,79 @@
-use crate::steps::DataPoint;
+use alloc::{collections::VecDeque, vec::Vec};
 
-const SCORING_SIZE: usize = 35;
+use crate::steps::{DataPoint, Scalar, StepCounterConfig};
 
-pub fn scoring(input: impl IntoIterator<Item = DataPoint>) -> Vec<DataPoint> {
-    let input = input.into_iter().collect::<Vec<_>>();
+pub(crate) const SCORING_SIZE: usize = 35;
 
-    input
-        .windows(SCORING_SIZE)
-        .map(|data| {
-            let midpoint_index = data.len() / 2;
-            let midpoint = data
-                .get(midpoint_index)
-                .map(|this| this.magnitude)
-                .expect("Point is smaller than len");
-
-            let diff_left = data
-                .iter()
-                .take(midpoint_index)
-                .map(|this| midpoint - this.magnitude)
-                .sum::<f64>();
-
-            let diff_right = data
-                .iter()
-                .skip(midpoint_index + 1)
-                .map(|this| midpoint - this.magnitude)
-                .sum::<f64>();
-
-            DataPoint {
-                magnitude: (diff_right + diff_left) / ((SCORING_SIZE - 1) as f64),
-                timestamp: data[midpoint_index].timestamp,
-            }
+/// Scores midpoint of window by its mean difference with the rest of points.
+#[derive(Debug, Clone)]
+pub(crate) struct Scoring<T = f64> {
+    scoring_size: usize,
+    /// Number of points compared with midpoint
+    divisor: T,
+    window: VecDeque<DataPoint<T>>,
+}
+
+impl<T: Scalar> Scoring<T> {
+    pub fn new(config: &StepCounterConfig) -> Self {
+        Self {
+            scoring_size: config.scoring_size,
+            divisor: T::from_usize(config.scoring_size.saturating_sub(1)),
+            window: VecDeque::with_capacity(config.scoring_size + 1),
+        }
+    }
+
+    pub fn push(&mut self, point: DataPoint<T>) -> Option<DataPoint<T>> {
+        if self.scoring_size == 0 {
+            return None;
+        }
+
+        self.window.push_back(point);
+
+        if self.window.len() > self.scoring_size {
+            self.window.pop_front();
+        }
+
+        if self.window.len() < self.scoring_size {
+            return None;
+        }
+
+        let midpoint_index = self.window.len() / 2;
+        let midpoint = self
+            .window
+            .get(midpoint_index)
+            .expect("Point is smaller than len");
+
+        let diff_left = self
+            .window
+            .iter()
+            .take(midpoint_index)
+            .fold(T::ZERO, |sum, this| {
+                sum + (midpoint.magnitude - this.magnitude)
+            });
+
+        let diff_right = self
+            .window
+            .iter()
+            .skip(midpoint_index + 1)
+            .fold(T::ZERO, |sum, this| {
+                sum + (midpoint.magnitude - this.magnitude)
+            });
+
+        Some(DataPoint {
+            magnitude: (diff_right + diff_left) / self.divisor,
+            timestamp: midpoint.timestamp,
         })
+    }
+}
+
+pub fn scoring<T: Scalar>(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = DataPoint<T>>,
+) -> Vec<DataPoint<T>> {
+    let mut stage = Scoring::new(config);
+
+    input
+        .into_iter()
+        .filter_map(|this| stage.push(this))
         .collect()
 }
@@ -1,32 +1,52 @@
-use std::time::Duration;
+use alloc::vec::Vec;
+use core::time::Duration;
 
-use crate::steps::DataPoint;
+use crate::steps::{DataPoint, Scalar, StepCounterConfig};
 
-const TIME_THRESHOLD: Duration = Duration::from_millis(200);
+pub(crate) const TIME_THRESHOLD: Duration = Duration::from_millis(200);
 
-pub fn time_threshold(input: impl IntoIterator<Item = DataPoint>) -> Vec<DataPoint> {
-    let input = input.into_iter().collect::<Vec<_>>();
+/// Keeps only peaks which are at least [`StepCounterConfig::time_threshold`] apart.
+#[derive(Debug, Clone)]
+pub(crate) struct TimeThreshold<T = f64> {
+    time_threshold: Duration,
+    current: Option<DataPoint<T>>,
+}
 
-    if input.is_empty() {
-        return Vec::new();
+impl<T: Scalar> TimeThreshold<T> {
+    pub fn new(config: &StepCounterConfig) -> Self {
+        Self {
+            time_threshold: config.time_threshold,
+            current: None,
+        }
     }
 
-    let mut current = input.first().cloned().expect("not empty");
+    pub fn push(&mut self, point: DataPoint<T>) -> Option<DataPoint<T>> {
+        let Some(current) = &mut self.current else {
+            self.current = Some(point);
+            return None;
+        };
+
+        if point.timestamp.saturating_sub(current.timestamp) > self.time_threshold {
+            *current = point.clone();
+            return Some(point);
+        }
+
+        if point.magnitude > current.magnitude {
+            *current = point;
+        }
+
+        None
+    }
+}
+
+pub fn time_threshold<T: Scalar>(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = DataPoint<T>>,
+) -> Vec<DataPoint<T>> {
+    let mut stage = TimeThreshold::new(config);
 
     input
         .into_iter()
-        .skip(1)
-        .filter_map(|this| {
-            if (this.timestamp - current.timestamp) > TIME_THRESHOLD {
-                current = this.clone();
-                return Some(this);
-            }
-
-            if this.magnitude > current.magnitude {
-                current = this.clone();
-            }
-
-            None
-        })
+        .filter_map(|this| stage.push(this))
         .collect()
 }
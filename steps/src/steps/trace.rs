@@ -0,0 +1,66 @@
+use alloc::vec::Vec;
+
+use crate::steps::{
+    Accelerometer, DataPoint, StepCounterConfig, detection, filtering, intepolation, preprocessing,
+    scoring, time_threshold,
+};
+
+/// Signal after every stage of windowed peak detection,
+/// used to plot stages and debug missed steps.
+///
+/// Segments split by gaps are concatenated.
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PipelineTrace {
+    /// Magnitude after preprocessing stage
+    pub preprocessed: Vec<DataPoint>,
+    /// Magnitude after interpolation stage
+    pub interpolated: Vec<DataPoint>,
+    /// Magnitude after filtering stage
+    pub filtered: Vec<DataPoint>,
+    /// Score of points after scoring stage
+    pub scored: Vec<DataPoint>,
+    /// Peaks after detection stage
+    pub detected: Vec<DataPoint>,
+    /// Steps after time threshold stage
+    pub steps: Vec<DataPoint>,
+}
+
+impl PipelineTrace {
+    /// Number of detected steps
+    pub fn count(&self) -> usize {
+        self.steps.len()
+    }
+}
+
+/// Count steps with windowed peak detection using [`StepCounterConfig::default`]
+/// and keep output of every stage.
+pub fn steps_count_traced(input: impl IntoIterator<Item = Accelerometer>) -> PipelineTrace {
+    steps_count_traced_with(&StepCounterConfig::default(), input)
+}
+
+/// Count steps with windowed peak detection tuned by `config`
+/// and keep output of every stage.
+pub fn steps_count_traced_with(
+    config: &StepCounterConfig,
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> PipelineTrace {
+    let mut trace = PipelineTrace {
+        preprocessed: preprocessing::preprocessing(config, input),
+        ..Default::default()
+    };
+
+    for segment in intepolation::interpolation(config, trace.preprocessed.iter().cloned()) {
+        let after_filter = filtering::filtering(config, segment.iter().cloned());
+        let after_scoring = scoring::scoring(config, after_filter.iter().cloned());
+
+        trace.interpolated.extend(segment);
+        trace.filtered.extend(after_filter);
+        trace.scored.extend(after_scoring);
+    }
+
+    trace.detected = detection::detection(config, trace.scored.iter().cloned());
+    trace.steps = time_threshold::time_threshold(config, trace.detected.iter().cloned());
+
+    trace
+}
@@ -0,0 +1,101 @@
+use core::time::Duration;
+
+use crate::steps::Accelerometer;
+
+/// Standard gravity in m/s²
+pub const GRAVITY: f64 = 9.80665;
+
+/// Unit of acceleration.
+///
+/// Pipelines are tuned for data in g, e.g. Android gives m/s² and should be converted
+/// with [`Accelerometer::new`] or [`Accelerometer::to_unit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum AccelerationUnit {
+    /// Standard gravity, used by `assets/wrist_25hz`
+    #[default]
+    G,
+    /// Meters per second squared, used by Android `SensorManager`
+    MetersPerSecondSquared,
+}
+
+impl AccelerationUnit {
+    /// Convert `value` in this unit to `unit`
+    pub fn convert(self, value: f64, unit: Self) -> f64 {
+        match (self, unit) {
+            (Self::G, Self::MetersPerSecondSquared) => value * GRAVITY,
+            (Self::MetersPerSecondSquared, Self::G) => value / GRAVITY,
+            _ => value,
+        }
+    }
+}
+
+impl Accelerometer {
+    /// Sample with axes in `unit` converted to g which pipelines expect.
+    ///
+    /// ```
+    /// use core::time::Duration;
+    ///
+    /// use steps::{Accelerometer, AccelerationUnit, GRAVITY};
+    ///
+    /// let sample = Accelerometer::new(
+    ///     Duration::ZERO,
+    ///     [0.0, 0.0, GRAVITY],
+    ///     AccelerationUnit::MetersPerSecondSquared,
+    /// );
+    ///
+    /// assert_eq!(sample.z, 1.0);
+    /// ```
+    pub fn new(timestamp: Duration, [x, y, z]: [f64; 3], unit: AccelerationUnit) -> Self {
+        Self {
+            timestamp,
+            x: unit.convert(x, AccelerationUnit::G),
+            y: unit.convert(y, AccelerationUnit::G),
+            z: unit.convert(z, AccelerationUnit::G),
+        }
+    }
+
+    /// Convert axes of sample in `from` unit to `to` unit
+    pub fn to_unit(self, from: AccelerationUnit, to: AccelerationUnit) -> Self {
+        Self {
+            timestamp: self.timestamp,
+            x: from.convert(self.x, to),
+            y: from.convert(self.y, to),
+            z: from.convert(self.z, to),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let sample = Accelerometer {
+            timestamp: Duration::from_secs(1),
+            x: 0.5,
+            y: -0.25,
+            z: 1.0,
+        };
+
+        let converted = sample.clone().to_unit(
+            AccelerationUnit::G,
+            AccelerationUnit::MetersPerSecondSquared,
+        );
+
+        assert_eq!(converted.z, GRAVITY);
+        assert_eq!(
+            Accelerometer::new(
+                converted.timestamp,
+                [converted.x, converted.y, converted.z],
+                AccelerationUnit::MetersPerSecondSquared
+            ),
+            sample
+        );
+    }
+}
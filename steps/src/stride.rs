@@ -0,0 +1,284 @@
+//! Step length and distance from accelerometer.
+//!
+//! Length of every step detected by windowed peak detection is estimated by Weinberg model:
+//!
+//! ```norust
+//! L = K * (a_max - a_min) ^ (1/4)
+//! ```
+//!
+//! where:
+//!
+//! - a_max, a_min – Maximal and minimal magnitude of filtered acceleration in m/s² around the step;
+//! - K – Calibration constant which depends on person and placement of device;
+//! - L – Step length in meters.
+
+use alloc::vec::Vec;
+use core::time::Duration;
+
+use crate::{
+    AccelerationUnit, Accelerometer, DataPoint, Distance, StepCounterConfig,
+    steps_count_traced_with,
+};
+
+#[cfg(not(any(feature = "std", test)))]
+use crate::math::Float;
+
+/// Weinberg constant which gives ~0.7m median step on wrist 25Hz dataset
+pub const WEINBERG_K: f64 = 0.45;
+
+/// Parameters of step length estimation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct StrideConfig {
+    /// Config of peak detection which detects steps
+    pub step_counter: StepCounterConfig,
+    /// Weinberg constant, see [`StrideConfig::calibrate`] to fit it for person
+    pub k: f64,
+    /// Unit of input acceleration
+    pub unit: AccelerationUnit,
+}
+
+impl Default for StrideConfig {
+    fn default() -> Self {
+        Self {
+            step_counter: StepCounterConfig::default(),
+            k: WEINBERG_K,
+            unit: AccelerationUnit::G,
+        }
+    }
+}
+
+impl StrideConfig {
+    /// Fit Weinberg constant of person from recording with known walked distance.
+    ///
+    /// Returns `None` if no steps are detected.
+    ///
+    /// # Params
+    /// - `input` - accelerometer data sorted by timestamp in asc order
+    /// - `distance` - real distance walked during recording e.g. length of treadmill session
+    pub fn calibrate(
+        self,
+        input: impl IntoIterator<Item = Accelerometer>,
+        distance: Distance,
+    ) -> Option<Self> {
+        let sum = peak_to_valley(&self, input)
+            .into_iter()
+            .map(|(_, this)| this.powf(0.25))
+            .sum::<f64>();
+
+        (sum > 0.0).then(|| Self {
+            k: distance.as_meters() / sum,
+            ..self
+        })
+    }
+}
+
+/// Detected step with its estimated length.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stride {
+    /// UNIX timestamp of the step, same time base as input [`Accelerometer::timestamp`]
+    pub timestamp: Duration,
+    /// Length of the step in meters
+    pub length: f64,
+}
+
+/// Detect steps and estimate length of every step.
+///
+/// # Params
+/// - `config` - config of peak detection and Weinberg model
+/// - `input` - accelerometer data sorted by timestamp in asc order
+pub fn strides(
+    config: &StrideConfig,
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> Vec<Stride> {
+    peak_to_valley(config, input)
+        .into_iter()
+        .map(|(timestamp, this)| Stride {
+            timestamp,
+            length: config.k * this.powf(0.25),
+        })
+        .collect()
+}
+
+/// Distance walked during recording without GPS e.g. indoor or on treadmill.
+///
+/// # Params
+/// - `config` - config of peak detection and Weinberg model
+/// - `input` - accelerometer data sorted by timestamp in asc order
+pub fn distance_from_accelerometer(
+    config: &StrideConfig,
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> Distance {
+    let meters = strides(config, input)
+        .iter()
+        .map(|this| this.length)
+        .sum::<f64>();
+
+    Distance::from_kilometers(meters / 1000.0)
+}
+
+/// Difference between maximal and minimal filtered acceleration in m/s² around every step.
+///
+/// Window of the step spans half of interval to previous and next steps.
+fn peak_to_valley(
+    config: &StrideConfig,
+    input: impl IntoIterator<Item = Accelerometer>,
+) -> Vec<(Duration, f64)> {
+    let trace = steps_count_traced_with(&config.step_counter, input);
+
+    let steps = trace
+        .steps
+        .iter()
+        .map(|this| this.timestamp)
+        .collect::<Vec<_>>();
+
+    // `filtered` is concatenation of segments, time goes backwards only between them
+    let mut segments = trace
+        .filtered
+        .chunk_by(|this, next| this.timestamp < next.timestamp);
+    let mut segment = segments.next().unwrap_or_default();
+    let mut start = 0;
+
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, &step)| {
+            // neighbour steps from other side of time going backwards are ignored
+            let previous = i
+                .checked_sub(1)
+                .and_then(|previous| step.checked_sub(steps[previous]))
+                .filter(|this| !this.is_zero());
+            let next = steps
+                .get(i + 1)
+                .and_then(|next| next.checked_sub(step))
+                .filter(|this| !this.is_zero());
+
+            let before = previous
+                .or(next)
+                .unwrap_or(config.step_counter.time_threshold)
+                / 2;
+            let after = next.or(previous).unwrap_or(before * 2) / 2;
+
+            if i > 0 && step < steps[i - 1] {
+                segment = segments
+                    .find(|this| this.last().is_some_and(|last| last.timestamp >= step))
+                    .unwrap_or_default();
+                start = 0;
+            }
+
+            while start < segment.len() && segment[start].timestamp < step.saturating_sub(before) {
+                start += 1;
+            }
+
+            let (min, max) = segment[start..]
+                .iter()
+                .take_while(|this| this.timestamp <= step + after)
+                .map(|DataPoint { magnitude, .. }| *magnitude)
+                .fold((f64::MAX, f64::MIN), |(min, max), this| {
+                    (min.min(this), max.max(this))
+                });
+
+            (
+                step,
+                config.unit.convert(
+                    (max - min).max(0.0),
+                    AccelerationUnit::MetersPerSecondSquared,
+                ),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Walking with steps every `period` and vertical acceleration of `amplitude` g
+    fn walking(period: f64, amplitude: f64, seconds: u64) -> Vec<Accelerometer> {
+        (0..seconds * 25)
+            .map(|i| {
+                let t = i as f64 / 25.0;
+
+                Accelerometer {
+                    timestamp: Duration::from_millis(i * 40),
+                    x: 0.0,
+                    y: 0.0,
+                    z: 1.0 + amplitude * (2.0 * core::f64::consts::PI * t / period).sin(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stronger_steps_are_longer() {
+        let config = StrideConfig::default();
+
+        let weak = strides(&config, walking(0.5, 0.2, 60));
+        let strong = strides(&config, walking(0.5, 0.6, 60));
+
+        assert!(!weak.is_empty() && !strong.is_empty());
+
+        let mean =
+            |this: &[Stride]| this.iter().map(|this| this.length).sum::<f64>() / this.len() as f64;
+
+        assert!(mean(&weak) < mean(&strong));
+        assert!(0.3 < mean(&weak) && mean(&strong) < 1.5);
+    }
+
+    #[test]
+    fn calibration() {
+        let data = walking(0.5, 0.4, 60);
+
+        let config = StrideConfig::default()
+            .calibrate(data.clone(), Distance::from_kilometers(0.08))
+            .unwrap();
+
+        let actual = distance_from_accelerometer(&config, data);
+
+        assert!((actual.as_meters() - 80.0).abs() < 1e-6);
+        assert_ne!(config.k, WEINBERG_K);
+    }
+
+    #[test]
+    fn no_steps() {
+        let data = (0..1000).map(|i| Accelerometer {
+            timestamp: Duration::from_millis(i * 40),
+            x: 0.0,
+            y: 0.0,
+            z: 1.0,
+        });
+
+        assert_eq!(
+            StrideConfig::default().calibrate(data.clone(), Distance::from_kilometers(1.0)),
+            None
+        );
+        assert_eq!(
+            distance_from_accelerometer(&StrideConfig::default(), data),
+            Distance::from_kilometers(0.0)
+        );
+    }
+
+    #[test]
+    fn time_going_backwards() {
+        let config = StrideConfig::default();
+
+        let mut data = walking(0.5, 0.4, 30);
+        data.extend(walking(0.5, 0.4, 30));
+
+        let once = strides(&config, walking(0.5, 0.4, 30));
+        let twice = strides(&config, data);
+
+        assert!(
+            twice
+                .windows(2)
+                .any(|this| this[1].timestamp < this[0].timestamp)
+        );
+        assert!(twice.len().abs_diff(once.len() * 2) <= 2);
+        assert!(twice.iter().all(|this| this.length > 0.3), "{twice:?}");
+    }
+}